@@ -33,6 +33,15 @@ def load_cheat_chunk_info(cheat_file_path: str):
         return exception_response(e)
 
 
+@eel.expose
+def load_cheat_chunk_page(cheat_file_path: str, offset: int = 0, limit: int = 50):
+    from module.cheats import load_cheat_chunk_page
+    try:
+        return success_response(load_cheat_chunk_page(cheat_file_path, offset, limit))
+    except Exception as e:
+        return exception_response(e)
+
+
 @eel.expose
 def update_current_cheats(enable_titles: List[str], cheat_file_path: str):
     from module.cheats import update_current_cheats
@@ -49,3 +58,58 @@ def open_cheat_mod_folder(folder_path: str):
         return success_response(open_cheat_mod_folder(folder_path))
     except Exception as e:
         return exception_response(e)
+
+
+@eel.expose
+def open_cheat_folder_for_title(title_id: str, emu_type: str = 'yuzu'):
+    from module.cheats import open_cheat_folder_for_title
+    try:
+        return success_response(open_cheat_folder_for_title(title_id, emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def set_cheats_enabled(title_id: str, build_id: str, enable_titles: List[str], emu_type: str = 'yuzu'):
+    from module.cheats import set_cheats_enabled
+    try:
+        return success_response(set_cheats_enabled(title_id, build_id, enable_titles, emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def rescan_cheats(force: bool = False):
+    from module.cheats import rescan_cheats
+    from module.yuzu import get_yuzu_load_path
+    try:
+        return success_response(rescan_cheats(get_yuzu_load_path(), force))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def refresh_game_data(force: bool = False):
+    from module.cheats import refresh_game_data
+    try:
+        return success_response(len(refresh_game_data(force)))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def list_mods(title_id: str, emu_type: str = 'yuzu'):
+    from module.mods import list_mods
+    try:
+        return success_response(list_mods(title_id, emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def set_mod_enabled(title_id: str, mod_name: str, enabled: bool, emu_type: str = 'yuzu'):
+    from module.mods import set_mod_enabled
+    try:
+        return success_response(set_mod_enabled(title_id, mod_name, enabled, emu_type))
+    except Exception as e:
+        return exception_response(e)
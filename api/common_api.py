@@ -38,7 +38,16 @@ def update_dark_state(dark):
 def detect_firmware_version(emu_type: str):
     from module.firmware import detect_firmware_version
     try:
-        detect_firmware_version(emu_type)
+        return success_response(detect_firmware_version(emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def uninstall_firmware(emu_type: str):
+    from module.firmware import uninstall_firmware
+    try:
+        uninstall_firmware(emu_type)
         return success_response()
     except Exception as e:
         return exception_response(e)
@@ -52,17 +61,33 @@ def get_config():
 
 @eel.expose
 def open_url_in_default_browser(url):
-    import webbrowser
-    webbrowser.open(url, new=0, autoraise=True)
+    from utils.common import open_url
+    try:
+        open_url(url)
+        return success_response()
+    except Exception as e:
+        return exception_response(e)
 
 
 @eel.expose
 def update_setting(setting: Dict[str, object]):
     from config import config, update_setting
     update_setting(setting)
+    from module.downloader import apply_global_options
+    apply_global_options()
     return success_response(config.to_dict())
 
 
+@eel.expose
+def reset_settings_to_default():
+    from config import config, reset_settings_to_default
+    try:
+        reset_settings_to_default()
+        return success_response(config.to_dict())
+    except Exception as e:
+        return exception_response(e)
+
+
 @eel.expose
 def get_net_release_info_by_tag(tag: str):
     from repository.my_info import get_release_info_by_tag
@@ -70,3 +95,212 @@ def get_net_release_info_by_tag(tag: str):
         return success_response(get_release_info_by_tag(tag))
     except Exception as e:
         return exception_response(e)
+
+
+@eel.expose
+def backup_emulator_config(emu_type: str):
+    from module.emu_config import backup_emulator_config
+    try:
+        return success_response(backup_emulator_config(emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def list_emulator_config_backups(emu_type: str):
+    from module.emu_config import list_emulator_config_backups
+    try:
+        return success_response(list_emulator_config_backups(emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def read_emulator_settings_summary(emu_type: str):
+    from module.emu_config import read_yuzu_settings_summary, read_ryujinx_settings_summary
+    try:
+        if emu_type == 'yuzu':
+            return success_response(read_yuzu_settings_summary())
+        return success_response(read_ryujinx_settings_summary())
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def restore_emulator_config(emu_type: str, backup_file_path: str):
+    from module.emu_config import restore_emulator_config
+    try:
+        restore_emulator_config(emu_type, backup_file_path)
+        return success_response()
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def list_installed_games(emu_type: str):
+    from module.games import list_installed_games
+    try:
+        return success_response(list_installed_games(emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def run_network_diagnostics():
+    from utils.network import run_network_diagnostics
+    try:
+        return success_response(run_network_diagnostics())
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def get_api_rate_status():
+    from utils.network import get_api_rate_status
+    return success_response(get_api_rate_status())
+
+
+@eel.expose
+def get_available_github_mirrors():
+    from utils.network import get_available_github_mirrors
+    return success_response(get_available_github_mirrors())
+
+
+@eel.expose
+def get_available_firmware_sources():
+    from utils.network import get_available_firmware_sources
+    return success_response(get_available_firmware_sources())
+
+
+@eel.expose
+def is_package_cached(emu_type: str, branch: str, version: str):
+    from module.common import is_package_cached
+    try:
+        return success_response(is_package_cached(emu_type, branch, version))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def setup_all(emu_type: str, version: str, firmware_version: str = None, branch: str = None):
+    from module.common import setup_all
+    try:
+        return success_response(setup_all(emu_type, version, firmware_version, branch))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def check_all_updates():
+    from module.common import check_all_updates
+    try:
+        return success_response(check_all_updates())
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def get_install_history(kind: str = None, emu_type: str = None):
+    from module.install_history import get_install_history
+    try:
+        return success_response(get_install_history(kind, emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def clear_install_history(kind: str = None):
+    from module.install_history import clear_install_history
+    try:
+        return success_response(clear_install_history(kind))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def get_install_step_timings():
+    from module.install_control import get_install_step_timings
+    return success_response(get_install_step_timings())
+
+
+@eel.expose
+def preview_emulator_paths(base_path: str, emu_type: str):
+    from module.common import preview_emulator_paths
+    try:
+        return success_response(preview_emulator_paths(base_path, emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def export_preset():
+    from module.preset import export_preset
+    try:
+        return success_response(export_preset())
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def apply_preset(preset):
+    from module.preset import apply_preset
+    try:
+        return success_response(apply_preset(preset))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def get_post_firmware_guidance(emu_type: str):
+    from module.firmware import get_post_firmware_guidance
+    try:
+        return success_response(get_post_firmware_guidance(emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def get_installed_summary():
+    from module.common import get_installed_summary
+    try:
+        return success_response(get_installed_summary())
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def verify_installation(emu_type: str):
+    from module.common import verify_installation
+    try:
+        return success_response(verify_installation(emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def get_keys_status(emu_type: str):
+    from module.keys import get_keys_status
+    try:
+        return success_response(get_keys_status(emu_type))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def pause_installation():
+    from module.install_control import pause_installation
+    pause_installation()
+    return success_response()
+
+
+@eel.expose
+def resume_installation():
+    from module.install_control import resume_installation
+    resume_installation()
+    return success_response()
+
+
+@eel.expose
+def is_installation_paused():
+    from module.install_control import is_installation_paused
+    return success_response(is_installation_paused())
@@ -0,0 +1,77 @@
+from api.common_response import *
+
+import eel
+
+
+@eel.expose
+def list_downloads():
+    from module.downloader import list_downloads
+    try:
+        return success_response(list_downloads())
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def pause_download(gid: str):
+    from module.downloader import pause_download
+    try:
+        return success_response(pause_download(gid))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def resume_download(gid: str):
+    from module.downloader import resume_download
+    try:
+        return success_response(resume_download(gid))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def cancel_download(gid: str):
+    from module.downloader import cancel_download
+    try:
+        return success_response(cancel_download(gid))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def cancel_all_downloads(remove_files: bool = False):
+    from module.downloader import cancel_all_downloads
+    try:
+        return success_response(cancel_all_downloads(remove_files))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def init_aria2():
+    from module.downloader import init_aria2
+    try:
+        init_aria2()
+        return success_response()
+    except Exception as e:
+        # aria2 init is idempotent, frontend could call it again to retry
+        return dict(exception_response(e), retryable=True)
+
+
+@eel.expose
+def restart_aria2(force: bool = False):
+    from module.downloader import restart_aria2
+    try:
+        return success_response(restart_aria2(force))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def summarize_aria2_log():
+    from module.downloader import summarize_aria2_log
+    try:
+        return success_response(summarize_aria2_log())
+    except Exception as e:
+        return exception_response(e)
@@ -27,16 +27,19 @@ def ask_and_update_ryujinx_path():
     logger.info(f'select folder: {folder}')
     if folder:
         from config import update_ryujinx_path
-        update_ryujinx_path(folder)
+        try:
+            update_ryujinx_path(folder)
+        except Exception as e:
+            return exception_response(e)
         return success_response(msg=f'修改 ryujinx 目录至 {folder}')
     else:
         return error_response(100, '修改已取消')
 
 
 @eel.expose
-def get_ryujinx_release_infos():
+def get_ryujinx_release_infos(limit=None, stable_only=False, since=None, until=None):
     try:
-        return success_response(get_all_ryujinx_release_infos())
+        return success_response(get_all_ryujinx_release_infos(limit, stable_only, since, until))
     except Exception as e:
         return exception_response(e)
 
@@ -61,12 +64,15 @@ def start_ryujinx():
 
 
 @eel.expose
-def install_ryujinx(version, branch):
+def install_ryujinx(version, branch, dry_run=False, allow_conflict=False):
     if not version or version == '':
         return {'msg': f'无效的版本 {version}'}
     from module.ryujinx import install_ryujinx_by_version
     try:
-        return success_response(msg=install_ryujinx_by_version(version, branch))
+        if dry_run:
+            return success_response(install_ryujinx_by_version(version, branch, dry_run=True))
+        res = install_ryujinx_by_version(version, branch, allow_conflict=allow_conflict)
+        return success_response(res, res['msg'])
     except Exception as e:
         return exception_response(e)
 
@@ -77,7 +83,9 @@ def install_ryujinx_firmware(version):
         return {'msg': f'无效的版本 {version}'}
     from module.ryujinx import install_firmware_to_ryujinx
     try:
-        return success_response(msg=install_firmware_to_ryujinx(version))
+        install_firmware_to_ryujinx(version)
+        from module.firmware import get_post_firmware_guidance
+        return success_response(get_post_firmware_guidance('ryujinx'))
     except Exception as e:
         return exception_response(e)
 
@@ -96,10 +104,29 @@ def switch_ryujinx_branch():
 
 
 @eel.expose
-def load_ryujinx_change_log():
+def load_ryujinx_change_log(max_versions=None):
     from repository.ryujinx import load_ryujinx_change_log
     try:
-        return success_response(load_ryujinx_change_log())
+        return success_response(load_ryujinx_change_log(max_versions))
     except Exception as e:
         return exception_response(e)
 
+
+
+@eel.expose
+def set_ryujinx_portable(enabled: bool, migrate: bool = True):
+    from module.ryujinx import set_ryujinx_portable
+    try:
+        return success_response(set_ryujinx_portable(enabled, migrate))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def uninstall_ryujinx(keep_user_data: bool = True):
+    from module.ryujinx import uninstall_ryujinx
+    try:
+        uninstall_ryujinx(keep_user_data)
+        return success_response(config.ryujinx.to_dict())
+    except Exception as e:
+        return exception_response(e)
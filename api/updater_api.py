@@ -11,6 +11,13 @@ def check_update():
     return success_response(has_update, latest_version)
 
 
+@eel.expose
+def skip_update_version(version: str):
+    from config import update_skipped_version
+    update_skipped_version(version)
+    return success_response()
+
+
 @eel.expose
 def download_net_by_tag(tag: str):
     from module.updater import download_net_by_tag
@@ -30,9 +37,9 @@ def update_net_by_tag(tag: str):
 
 
 @eel.expose
-def load_change_log():
+def load_change_log(max_versions=None):
     from repository.my_info import load_change_log
     try:
-        return success_response(load_change_log())
+        return success_response(load_change_log(max_versions))
     except Exception as e:
         exception_response(e)
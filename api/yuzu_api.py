@@ -26,7 +26,10 @@ def ask_and_update_yuzu_path():
     logger.info(f'select folder: {folder}')
     if folder:
         from config import update_yuzu_path
-        update_yuzu_path(folder)
+        try:
+            update_yuzu_path(folder)
+        except Exception as e:
+            return exception_response(e)
         return success_response(msg=f'修改 yuzu 目录至 {folder}')
     else:
         return error_response(100, '修改已取消')
@@ -52,12 +55,15 @@ def start_yuzu():
 
 
 @eel.expose
-def install_yuzu(version, branch):
+def install_yuzu(version, branch, dry_run=False, allow_conflict=False):
     if not version or version == '':
         return error_response(404, f'无效的版本 {version}')
     from module.yuzu import install_yuzu
     try:
-        return success_response(msg=install_yuzu(version, branch))
+        if dry_run:
+            return success_response(install_yuzu(version, branch, dry_run=True))
+        res = install_yuzu(version, branch, allow_conflict=allow_conflict)
+        return success_response(res, res['msg'])
     except Exception as e:
         return exception_response(e)
 
@@ -68,7 +74,9 @@ def install_yuzu_firmware(version):
         return error_response(404, f'无效的版本 {version}')
     from module.yuzu import install_firmware_to_yuzu
     try:
-        return success_response(msg=install_firmware_to_yuzu(version))
+        install_firmware_to_yuzu(version)
+        from module.firmware import get_post_firmware_guidance
+        return success_response(get_post_firmware_guidance('yuzu'))
     except Exception as e:
         return exception_response(e)
 
@@ -86,9 +94,28 @@ def switch_yuzu_branch():
 
 
 @eel.expose
-def get_all_yuzu_release_versions():
+def get_all_yuzu_release_versions(limit=None, stable_only=False, since=None, until=None):
     from repository.yuzu import get_all_yuzu_release_versions
     try:
-        return success_response(get_all_yuzu_release_versions(config.yuzu.branch))
+        return success_response(get_all_yuzu_release_versions(config.yuzu.branch, limit, stable_only, since, until))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def set_yuzu_portable(enabled: bool, migrate: bool = True):
+    from module.yuzu import set_yuzu_portable
+    try:
+        return success_response(set_yuzu_portable(enabled, migrate))
+    except Exception as e:
+        return exception_response(e)
+
+
+@eel.expose
+def uninstall_yuzu(keep_user_data: bool = True):
+    from module.yuzu import uninstall_yuzu
+    try:
+        uninstall_yuzu(keep_user_data)
+        return success_response(config.yuzu.to_dict())
     except Exception as e:
         return exception_response(e)
@@ -1,7 +1,7 @@
 import json
 import os
-from dataclasses import dataclass
-from typing import Optional, Dict
+from dataclasses import dataclass, field
+from typing import Optional, Dict, List
 from pathlib import Path
 from dataclasses_json import dataclass_json, Undefined
 import logging
@@ -26,7 +26,9 @@ logging.basicConfig(
 )
 logger = logging.getLogger(__name__)
 config_path = Path('config.json')
+config_backup_path = Path('config.json.bak')
 config = None
+config_mtime = None
 
 
 # def log_exception(exctype, value, traceback):
@@ -60,6 +62,10 @@ class NetworkSetting:
     firmwareSource: Optional[str] = 'auto-detect'
     githubApiMode: Optional[str] = 'direct'
     githubDownloadSource: Optional[str] = 'self'
+    customFirmwareSources: Optional[List[Dict[str, str]]] = field(default_factory=list)
+    yuzuRepoCandidates: Optional[Dict[str, List[str]]] = field(default_factory=dict)
+    ryujinxRepo: Optional[str] = 'Ryubing/Ryujinx'
+    mirrorPriority: Optional[List[str]] = field(default_factory=list)
 
 
 @dataclass_json
@@ -67,6 +73,18 @@ class NetworkSetting:
 class DownloadSetting:
     autoDeleteAfterInstall: Optional[bool] = True
     disableAria2Ipv6: Optional[bool] = True
+    networkRecoveryTimeout: Optional[int] = 300
+    connectTimeout: Optional[int] = 10
+    readTimeout: Optional[int] = 30
+    saveDir: Optional[str] = None
+    fallbackDirectOnProxyError: Optional[bool] = False
+
+
+@dataclass_json
+@dataclass
+class UpdateSetting:
+    channel: Optional[str] = 'stable'
+    skippedVersion: Optional[str] = None
 
 
 @dataclass_json
@@ -74,6 +92,7 @@ class DownloadSetting:
 class UiSetting:
     lastOpenEmuPage: Optional[str] = 'yuzu',
     dark: Optional[bool] = True
+    language: Optional[str] = 'zh'
 
 
 @dataclass_json(undefined=Undefined.EXCLUDE)
@@ -82,6 +101,11 @@ class CommonSetting:
     ui: UiSetting = UiSetting()
     network: NetworkSetting = NetworkSetting()
     download: DownloadSetting = DownloadSetting()
+    update: UpdateSetting = UpdateSetting()
+
+
+# snapshot before any modification, default instances are shared with the loaded config
+default_setting_dict = CommonSetting().to_dict()
 
 
 @dataclass_json(undefined=Undefined.EXCLUDE)
@@ -90,23 +114,74 @@ class Config:
     yuzu: YuzuConfig = YuzuConfig()
     ryujinx: RyujinxConfig = RyujinxConfig()
     setting: CommonSetting = CommonSetting()
+    install_history: Optional[List[Dict[str, str]]] = field(default_factory=list)
+
+
+def load_config_file(path: Path):
+    if not path.exists():
+        return None
+    try:
+        with open(path, 'r', encoding='utf-8') as f:
+            return Config.from_dict(json.load(f))
+    except Exception as e:
+        logger.error(f'fail to load config from {path.absolute()}, ex: {e}')
+        return None
 
 
-if os.path.exists(config_path):
-    with open(config_path, 'r', encoding='utf-8') as f:
-        config = Config.from_dict(json.load(f))
+config = load_config_file(config_path)
+if not config and config_path.exists():
+    config = load_config_file(config_backup_path)
+    if config:
+        logger.warning(f'config file is corrupted, restored from backup: {config_backup_path.absolute()}')
+    else:
+        logger.warning(f'config file is corrupted and no valid backup found, using default config.')
 if not config:
     config = Config()
+if config_path.exists():
+    config_mtime = config_path.stat().st_mtime
+
+
+def reload_config_if_changed():
+    """
+    reload config from config.json if it has been changed outside
+    :return: True if config is reloaded
+    """
+    global config_mtime
+    if not config_path.exists():
+        return False
+    mtime = config_path.stat().st_mtime
+    if mtime == config_mtime:
+        return False
+    new_config = load_config_file(config_path)
+    config_mtime = mtime
+    if not new_config:
+        logger.warning(f'config file changed but fail to load, keep current config.')
+        return False
+    logger.info(f'config file changed outside, reloading config from {config_path.absolute()}')
+    # update in place since other modules hold the reference of config
+    for name in Config.__dataclass_fields__:
+        setattr(config, name, getattr(new_config, name))
+    return True
 
 
 def dump_config():
+    global config_mtime
     logger.info(f'saving config to {config_path.absolute()}')
-    with open(config_path, 'w', encoding='utf-8') as f:
+    tmp_path = config_path.with_name(config_path.name + '.tmp')
+    with open(tmp_path, 'w', encoding='utf-8') as f:
         f.write(config.to_json(ensure_ascii=False, indent=2))
+        f.flush()
+        os.fsync(f.fileno())
+    if load_config_file(config_path):
+        import shutil
+        shutil.copy2(config_path, config_backup_path)
+    os.replace(tmp_path, config_path)
+    config_mtime = config_path.stat().st_mtime
 
 
 def update_yuzu_path(new_yuzu_path: str):
-    new_path = Path(new_yuzu_path)
+    from utils.common import validate_install_path
+    new_path = validate_install_path(new_yuzu_path)
     if not new_path.exists():
         logger.info(f'create directory: {new_path}')
         new_path.mkdir(parents=True, exist_ok=True)
@@ -121,7 +196,8 @@ def update_yuzu_path(new_yuzu_path: str):
 
 
 def update_ryujinx_path(new_ryujinx_path: str):
-    new_path = Path(new_ryujinx_path)
+    from utils.common import validate_install_path
+    new_path = validate_install_path(new_ryujinx_path)
     if not new_path.exists():
         logger.info(f'create directory: {new_path}')
         new_path.mkdir(parents=True, exist_ok=True)
@@ -152,11 +228,26 @@ def update_dark_state(dark: bool):
     dump_config()
 
 
+def update_skipped_version(version: Optional[str]):
+    config.setting.update.skippedVersion = version
+    logger.info(f'update skippedVersion to {config.setting.update.skippedVersion}')
+    dump_config()
+
+
 def update_setting(setting: Dict[str, object]):
     logger.info(f'updating settings: {setting}')
     config.setting = CommonSetting.from_dict(setting)
     dump_config()
 
 
+def reset_settings_to_default():
+    import copy
+    logger.info('reset settings to default.')
+    config.setting = CommonSetting.from_dict(copy.deepcopy(default_setting_dict))
+    dump_config()
+    return config.setting
+
+
 __all__ = ['config', 'dump_config', 'update_yuzu_path', 'current_version', 'update_ryujinx_path',
-           'update_last_open_emu_page', 'update_setting']
+           'update_last_open_emu_page', 'update_dark_state', 'update_setting', 'update_skipped_version',
+           'load_config_file', 'reload_config_if_changed', 'reset_settings_to_default']
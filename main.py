@@ -1,5 +1,6 @@
 from config import config
 import argparse
+import sys
 
 
 def start_ui(mode=None):
@@ -7,12 +8,71 @@ def start_ui(mode=None):
     ui.main(mode=mode)
 
 
-if __name__ == '__main__':
+def create_parser():
     parser = argparse.ArgumentParser()
     parser.add_argument(
         "-m",
         "--mode",
         help="指定使用的浏览器",
     )
-    args = parser.parse_args()
+    parser.add_argument('--yuzu-path', help='设置 yuzu 安装目录')
+    parser.add_argument('--ryujinx-path', help='设置 Ryujinx 安装目录')
+    parser.add_argument('--install-yuzu', metavar='VERSION', help='安装指定版本的 yuzu')
+    parser.add_argument('--yuzu-branch', choices=['ea', 'mainline'], help='安装 yuzu 时使用的分支')
+    parser.add_argument('--install-ryujinx', metavar='VERSION', help='安装指定版本的 Ryujinx')
+    parser.add_argument('--ryujinx-branch', choices=['ava', 'mainline'], help='安装 Ryujinx 时使用的分支')
+    parser.add_argument('--install-firmware', metavar='VERSION', help='安装指定版本的固件, latest 表示最新版本')
+    parser.add_argument('--firmware-target', choices=['yuzu', 'ryujinx'], default='yuzu',
+                        help='固件安装至哪个模拟器, 默认为 yuzu')
+    return parser
+
+
+def is_headless(args):
+    return any([args.yuzu_path, args.ryujinx_path, args.install_yuzu, args.install_ryujinx, args.install_firmware])
+
+
+def run_headless(args):
+    """
+    :return: process exit code, non-zero if any install failed
+    """
+    from module.msg_notifier import update_notifier
+    from config import update_yuzu_path, update_ryujinx_path
+    update_notifier('console')
+    if args.yuzu_path:
+        update_yuzu_path(args.yuzu_path)
+    if args.ryujinx_path:
+        update_ryujinx_path(args.ryujinx_path)
+    if args.install_yuzu:
+        from module.yuzu import install_yuzu
+        print(install_yuzu(args.install_yuzu, args.yuzu_branch or config.yuzu.branch)['msg'])
+    if args.install_ryujinx:
+        from module.ryujinx import install_ryujinx_by_version
+        print(install_ryujinx_by_version(args.install_ryujinx, args.ryujinx_branch or config.ryujinx.branch)['msg'])
+    if args.install_firmware:
+        firmware_version = args.install_firmware
+        if firmware_version == 'latest':
+            from module.common import get_firmware_infos
+            firmware_version = get_firmware_infos()[0]['version']
+        if args.firmware_target == 'ryujinx':
+            from module.ryujinx import install_firmware_to_ryujinx
+            new_version = install_firmware_to_ryujinx(firmware_version)
+        else:
+            from module.yuzu import install_firmware_to_yuzu
+            new_version = install_firmware_to_yuzu(firmware_version)
+        if not new_version:
+            from utils.i18n import tr
+            print(tr('firmware.install_failed', version=firmware_version))
+            return 1
+    return 0
+
+
+if __name__ == '__main__':
+    args = create_parser().parse_args()
+    if is_headless(args):
+        try:
+            exit_code = run_headless(args)
+        except Exception as e:
+            print(f'error: {e}')
+            sys.exit(1)
+        sys.exit(exit_code)
     start_ui(args.mode)
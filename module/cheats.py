@@ -1,73 +1,209 @@
+import hashlib
+import json
 import re
 import shutil
 from pathlib import Path
-from typing import List, Dict
+from typing import List, Dict, Tuple
 from utils.network import session
 import logging
 import time
-from functools import lru_cache
 from module.msg_notifier import send_notify
 
 
 logger = logging.getLogger(__name__)
-cheat_item_re = re.compile(r'\[(.*?)]\n+([\n\ra-z0-9A-Z\s]+)', re.MULTILINE)
-multi_new_line_re = re.compile('(\r\n|\n){2,}')
+cheat_title_re = re.compile(r'^\[(.*)]$')
+master_code_title_re = re.compile(r'^\{(.*)}$')
+cheat_code_re = re.compile(r'^[\da-zA-Z\s]+$')
 cheat_file_re = re.compile(r'^[\dA-Za-z]{16}.[tT][xX][tT]$')
 game_id_re = re.compile(r'^[\dA-Za-z]{16}$')
+scan_result_cache_ttl = 60
+cheats_folder_cache: Dict[str, Tuple[float, bool]] = {}
+scan_result_cache = {}
 
 
-@lru_cache(1)
-def get_game_data():
-    res = {}
+game_data_url = 'https://cdn.jsdelivr.net/gh/triwinds/ns-emu-tools@main/game_data.json'
+game_data_cache_path = Path('game_data_cache.json')
+game_data_cache_version = 1
+game_data_cache_ttl = 24 * 60 * 60
+game_data = None
+cheat_chunk_root = Path('cheats_chunk')
+
+
+def _load_game_data_cache():
+    if not game_data_cache_path.exists():
+        return None
+    try:
+        with open(game_data_cache_path, 'r', encoding='utf-8') as f:
+            cache = json.load(f)
+        if cache.get('version') == game_data_cache_version and isinstance(cache.get('data'), dict):
+            return cache
+        logger.info(f'game data cache version mismatch, ignore it.')
+    except Exception as e:
+        logger.warning(f'fail to load game data cache, ex: {e}')
+    return None
+
+
+def _save_game_data_cache(data):
+    cache = {'version': game_data_cache_version, 'time': time.time(), 'data': data}
+    with open(game_data_cache_path, 'w', encoding='utf-8') as f:
+        json.dump(cache, f, ensure_ascii=False)
+
+
+def refresh_game_data(force=False):
+    global game_data
+    cache = _load_game_data_cache()
+    if not force and cache and time.time() - cache.get('time', 0) < game_data_cache_ttl:
+        logger.info('using cached game data.')
+        game_data = cache['data']
+        return game_data
     try:
-        resp = session.get('https://cdn.jsdelivr.net/gh/triwinds/ns-emu-tools@main/game_data.json')
-        return resp.json()
+        resp = session.get(game_data_url, timeout=10)
+        data = resp.json()
+        _save_game_data_cache(data)
+        game_data = data
+        logger.info(f'game data refreshed, count: {len(data)}')
     except Exception as e:
         logger.warning(f'fail to load game data, ex: {e}')
-    return res
+        if cache:
+            logger.info('network unavailable, fallback to cached game data.')
+            send_notify('获取游戏数据失败, 使用本地缓存的游戏数据')
+            game_data = cache['data']
+        elif game_data is None:
+            game_data = {}
+    return game_data
+
+
+def get_game_data():
+    if game_data is None:
+        return refresh_game_data()
+    return game_data
 
 
-def scan_all_cheats_folder(mod_path) -> List[Dict[str, str]]:
+def _has_cheat_file(folder: Path):
+    key = str(folder.absolute())
+    mtime = folder.stat().st_mtime
+    cached = cheats_folder_cache.get(key)
+    if cached and cached[0] == mtime:
+        return cached[1]
+    has_cheat_file = False
+    for file in folder.glob('*.[tT][xX][tT]'):
+        if cheat_file_re.match(file.name):
+            has_cheat_file = True
+            break
+    cheats_folder_cache[key] = (mtime, has_cheat_file)
+    return has_cheat_file
+
+
+def scan_all_cheats_folder(mod_path, force=False) -> List[Dict[str, str]]:
     root = Path(mod_path)
+    cache_key = str(root.absolute())
+    if not force and scan_result_cache.get('key') == cache_key \
+            and time.time() - scan_result_cache['time'] < scan_result_cache_ttl:
+        logger.info(f'using cached cheats scan result of path: {root}')
+        return list(scan_result_cache['result'])
+    if force:
+        cheats_folder_cache.clear()
     logger.info(f'scanning cheats under path: {root}')
-    cheats_folders = root.glob('**/cheats')
     game_data = get_game_data()
     res = []
-    for folder in cheats_folders:
-        game_id = folder.parent.parent.name
-        if game_id_re.match(game_id) is None:
+    if not root.exists():
+        return res
+    for game_folder in root.iterdir():
+        if not game_folder.is_dir() or game_id_re.match(game_folder.name) is None:
             continue
-        has_cheat_file = False
-        for file in folder.glob('*.[tT][xX][tT]'):
-            if cheat_file_re.match(file.name):
-                has_cheat_file = True
-                break
-        if has_cheat_file:
-            res.append({
-                'game_id': game_id,
-                'cheats_path': str(folder.absolute()),
-                'game_name': game_data.get(game_id)
-            })
-    return res
+        for folder in game_folder.glob('*/cheats'):
+            if folder.is_dir() and _has_cheat_file(folder):
+                res.append({
+                    'game_id': game_folder.name,
+                    'cheats_path': str(folder.absolute()),
+                    'game_name': game_data.get(game_folder.name)
+                })
+    scan_result_cache.update({'key': cache_key, 'time': time.time(), 'result': res})
+    return list(res)
+
+
+def rescan_cheats(mod_path, force=False):
+    scan_result_cache.clear()
+    return scan_all_cheats_folder(mod_path, force)
 
 
 def save_cheat_map_to_txt(cheats_map: Dict, txt_path: Path):
     with txt_path.open('w', encoding='utf-8') as f:
         for cheat_title in cheats_map:
             cheat_content = cheats_map[cheat_title]
-            f.write(f'[{cheat_title}]\n')
+            if master_code_title_re.match(cheat_title):
+                f.write(f'{cheat_title}\n')
+            else:
+                f.write(f'[{cheat_title}]\n')
             f.write(f'{cheat_content}\n\n')
 
 
-def parse_cheat_file(cheat_file: Path):
-    with open(cheat_file, 'r', encoding='utf-8') as f:
-        data = f.read()
-    groups = cheat_item_re.findall(data)
+def read_cheat_file_text(cheat_file: Path):
+    # only utf-8 and gbk are supported, other encodings are decoded as utf-8 with replacement characters
+    raw = cheat_file.read_bytes()
+    for encoding in ('utf-8-sig', 'gbk'):
+        try:
+            return raw.decode(encoding), encoding
+        except UnicodeDecodeError:
+            continue
+    return raw.decode('utf-8', errors='replace'), 'utf-8'
+
+
+def parse_cheat_file_with_warnings(cheat_file: Path):
+    data, encoding = read_cheat_file_text(cheat_file)
+    warnings = []
+    if encoding != 'utf-8-sig':
+        warnings.append(f'文件 {cheat_file.name} 使用 {encoding} 编码, 已自动转换')
     res = {}
-    for item in groups:
-        title, content = item
-        content = multi_new_line_re.sub('\n', content).rstrip().lstrip()
-        res[title] = content
+    title = None
+    codes = []
+    skipping_bad_block = False
+    for line_no, line in enumerate(data.splitlines(), 1):
+        line = line.strip()
+        if not line:
+            continue
+        title_match = cheat_title_re.match(line)
+        master_code_match = master_code_title_re.match(line)
+        if title_match or master_code_match:
+            if title is not None:
+                _put_cheat_item(res, title, codes, warnings)
+            # master code keeps its braces so it is written back as a master code block
+            title = title_match.group(1) if title_match else line
+            codes = []
+            skipping_bad_block = False
+        elif line.startswith(('[', '{')):
+            # codes of a broken header must not be merged into the previous cheat
+            if title is not None:
+                _put_cheat_item(res, title, codes, warnings)
+            title = None
+            codes = []
+            skipping_bad_block = True
+            warnings.append(f'第 {line_no} 行的金手指标题格式错误, 已跳过该金手指: {line}')
+        elif skipping_bad_block:
+            continue
+        elif title is None:
+            warnings.append(f'第 {line_no} 行不属于任何金手指, 已跳过: {line}')
+        elif cheat_code_re.match(line):
+            codes.append(line)
+        else:
+            warnings.append(f'第 {line_no} 行无法识别, 已跳过: {line}')
+    if title is not None:
+        _put_cheat_item(res, title, codes, warnings)
+    return res, warnings
+
+
+def _put_cheat_item(cheat_map: Dict, title: str, codes: List[str], warnings: List[str]):
+    if not codes:
+        warnings.append(f'金手指 [{title}] 没有内容')
+    if title in cheat_map:
+        warnings.append(f'金手指 [{title}] 重复出现, 将使用最后一个')
+    cheat_map[title] = '\n'.join(codes)
+
+
+def parse_cheat_file(cheat_file: Path):
+    res, warnings = parse_cheat_file_with_warnings(cheat_file)
+    for warning in warnings:
+        logger.warning(f'{cheat_file}: {warning}')
     return res
 
 
@@ -85,15 +221,38 @@ def list_all_cheat_files_from_folder(folder_path: str):
     return res
 
 
+def get_cheat_chunk_folder(cheat_file: Path):
+    # keep the chunk cache outside the emulator load folder, otherwise ryujinx would treat it as a mod
+    cheats_folder = cheat_file.absolute().parent
+    folder_hash = hashlib.md5(str(cheats_folder).lower().encode('utf-8')).hexdigest()[:8]
+    return cheat_chunk_root.joinpath(f'{cheats_folder.parent.name}_{folder_hash}')
+
+
+def _migrate_legacy_chunk_file(cheat_file: Path, chunk_file: Path):
+    legacy_chunk_file = cheat_file.parent.parent.joinpath('cheats_chunk', chunk_file.name)
+    if chunk_file.exists() or not legacy_chunk_file.exists():
+        return
+    logger.info(f'moving legacy chunk file {legacy_chunk_file} to {chunk_file}')
+    shutil.move(str(legacy_chunk_file), str(chunk_file))
+    legacy_folder = legacy_chunk_file.parent
+    if not any(legacy_folder.iterdir()):
+        legacy_folder.rmdir()
+
+
 def load_cheat_chunk_info(cheat_file_path: str):
     cheat_file = Path(cheat_file_path)
     if not cheat_file.exists():
         raise RuntimeError(f'文件 {cheat_file} 不存在.')
-    chunk_folder = cheat_file.parent.parent.joinpath('cheats_chunk')
+    chunk_folder = get_cheat_chunk_folder(cheat_file)
     if not chunk_folder.exists():
         chunk_folder.mkdir(parents=True, exist_ok=True)
     chunk_file = chunk_folder.joinpath(cheat_file.name[:16] + '_chunk.txt')
-    current_cheat_map = parse_cheat_file(cheat_file)
+    _migrate_legacy_chunk_file(cheat_file, chunk_file)
+    current_cheat_map, warnings = parse_cheat_file_with_warnings(cheat_file)
+    for warning in warnings:
+        logger.warning(f'{cheat_file}: {warning}')
+    if warnings:
+        send_notify(f'解析 {cheat_file.name} 时发现 {len(warnings)} 个问题, 详情请查看日志')
     logger.debug(f'current_cheat_map size: {len(current_cheat_map)}, '
                  f'current_cheat_map titles: {current_cheat_map.keys()}')
     if chunk_file.exists():
@@ -119,11 +278,20 @@ def load_cheat_chunk_info(cheat_file_path: str):
     return res
 
 
+def load_cheat_chunk_page(cheat_file_path: str, offset: int = 0, limit: int = 50):
+    res = load_cheat_chunk_info(cheat_file_path)
+    return {
+        'total': len(res),
+        'offset': offset,
+        'items': res[offset:offset + limit],
+    }
+
+
 def update_current_cheats(enable_titles: List[str], cheat_file_path: str):
     cheat_file = Path(cheat_file_path)
     if not cheat_file.exists():
         raise RuntimeError(f'文件 {cheat_file} 不存在.')
-    chunk_folder = cheat_file.parent.parent.joinpath('cheats_chunk')
+    chunk_folder = get_cheat_chunk_folder(cheat_file)
     if not chunk_folder.exists():
         raise RuntimeError(f'仓库目录 {chunk_folder} 不存在.')
     chunk_file = chunk_folder.joinpath(cheat_file.name[:16] + '_chunk.txt')
@@ -148,14 +316,68 @@ def update_current_cheats(enable_titles: List[str], cheat_file_path: str):
     save_cheat_map_to_txt(cheat_map, cheat_file)
 
 
+def get_mod_root_path(emu_type: str):
+    if emu_type == 'ryujinx':
+        from module.ryujinx import get_ryujinx_mods_path
+        return get_ryujinx_mods_path()
+    from module.yuzu import get_yuzu_load_path
+    return get_yuzu_load_path()
+
+
+def find_cheat_files(mod_path, title_id: str, build_id: str, emu_type: str = 'yuzu') -> List[Path]:
+    root = Path(mod_path)
+    res = []
+    if not root.exists():
+        return res
+    # ryujinx: mods/contents/<TID>/cheats, yuzu: load/<TID>/<mod name>/cheats
+    pattern = 'cheats/*.[tT][xX][tT]' if emu_type == 'ryujinx' else '*/cheats/*.[tT][xX][tT]'
+    for game_folder in root.iterdir():
+        if game_folder.name.lower() != title_id.lower():
+            continue
+        for txt_file in game_folder.glob(pattern):
+            if cheat_file_re.match(txt_file.name) and txt_file.name[:16].lower() == build_id[:16].lower():
+                res.append(txt_file)
+    return res
+
+
+def set_cheats_enabled(title_id: str, build_id: str, enable_titles: List[str], emu_type: str = 'yuzu'):
+    cheat_files = find_cheat_files(get_mod_root_path(emu_type), title_id, build_id, emu_type)
+    if not cheat_files:
+        raise RuntimeError(f'未找到游戏 [{title_id}] 中 [{build_id}] 对应的金手指文件.')
+    for cheat_file in cheat_files:
+        # make sure the chunk file keeps a copy of all cheats before overwriting
+        load_cheat_chunk_info(str(cheat_file))
+        update_current_cheats(enable_titles, str(cheat_file))
+    return [str(cheat_file.absolute()) for cheat_file in cheat_files]
+
+
 def open_cheat_mod_folder(folder_path: str):
     folder = Path(folder_path)
     if not folder.exists():
         raise RuntimeError(f'目录 {folder} 不存在.')
-    import subprocess
+    from utils.common import open_folder
     parent_folder = folder.parent
     logger.info(f'open folder [{parent_folder}] in explorer')
-    subprocess.Popen(f'explorer "{str(parent_folder.absolute())}"')
+    open_folder(parent_folder)
+
+
+def get_title_cheats_path(title_id: str, emu_type: str = 'yuzu'):
+    if not game_id_re.match(title_id):
+        raise RuntimeError(f'无效的 title id: {title_id}')
+    title_folder = Path(get_mod_root_path(emu_type)).joinpath(title_id.upper())
+    if emu_type == 'ryujinx':
+        return title_folder.joinpath('cheats')
+    for cheats_folder in title_folder.glob('*/cheats'):
+        if cheats_folder.is_dir():
+            return cheats_folder
+    return title_folder.joinpath('Cheats/cheats')
+
+
+def open_cheat_folder_for_title(title_id: str, emu_type: str = 'yuzu'):
+    from utils.common import open_folder
+    cheats_folder = get_title_cheats_path(title_id, emu_type)
+    logger.info(f'open folder [{cheats_folder}] in explorer')
+    return open_folder(cheats_folder, create=True)
 
 
 def main():
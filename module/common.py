@@ -6,16 +6,16 @@ from pathlib import Path
 from module.msg_notifier import send_notify
 from config import config
 import bs4
-from utils.network import get_finial_url, session
+from utils.network import get_finial_url, session, firmware_base_url
 import logging
-from module.downloader import download
+from module.downloader import download, firmware_download_priority
 
 logger = logging.getLogger(__name__)
 
 
 @lru_cache(1)
 def get_firmware_infos():
-    base_url = 'https://archive.org/download/nintendo-switch-global-firmwares/'
+    base_url = firmware_base_url
     resp = session.get(get_finial_url(base_url))
     soup = bs4.BeautifulSoup(resp.text, features="html.parser")
     a_tags = soup.select('#maincontent > div > div > pre > table > tbody > tr > td > a')
@@ -58,7 +58,8 @@ def check_and_install_msvc():
 
 
 def install_firmware(firmware_version, target_firmware_path):
-    send_notify('正在获取固件信息...')
+    from utils.i18n import tr
+    send_notify(tr('firmware.fetching_info'))
     firmware_infos = get_firmware_infos()
     target_info = None
     if firmware_version:
@@ -66,27 +67,328 @@ def install_firmware(firmware_version, target_firmware_path):
         target_info = firmware_map.get(firmware_version)
     if not target_info:
         logger.info(f'Target firmware version [{firmware_version}] not found, skip install.')
-        send_notify(f'Target firmware version [{firmware_version}] not found, skip install.')
+        send_notify(tr('firmware.not_found', version=firmware_version))
         return
     url = get_finial_url(target_info['url'])
-    send_notify(f'开始下载固件...')
+    send_notify(tr('firmware.start_download'))
     logger.info(f"downloading firmware of [{firmware_version}] from {url}")
-    info = download(url)
-    file = info.files[0]
-    import zipfile
-    with zipfile.ZipFile(file.path, 'r') as zf:
+    from module.install_control import wait_if_installation_paused, timed_install
+    with timed_install('firmware', tr('step.download_firmware')):
+        info = download(url, priority=firmware_download_priority)
+        file = info.files[0]
+        wait_if_installation_paused(tr('step.unzip_firmware'))
+        from utils.archive import extract_zip
         firmware_path = target_firmware_path
         shutil.rmtree(firmware_path, ignore_errors=True)
         firmware_path.mkdir(parents=True, exist_ok=True)
-        send_notify(f'开始解压安装固件...')
+        send_notify(tr('firmware.unzipping'))
         logger.info(f'Unzipping firmware files to {firmware_path}')
-        zf.extractall(firmware_path)
+        extract_zip(file.path, firmware_path)
         logger.info(f'Firmware of [{firmware_version}] install successfully.')
-    if config.setting.download.autoDeleteAfterInstall:
-        os.remove(file.path)
+        if config.setting.download.autoDeleteAfterInstall:
+            os.remove(file.path)
     return firmware_version
 
 
+def get_installed_summary():
+    from module.yuzu import get_yuzu_exe_path
+    from module.ryujinx import get_ryujinx_exe_path
+    from module.firmware import get_prod_keys_path
+    yuzu_path = Path(config.yuzu.yuzu_path)
+    ryujinx_path = Path(config.ryujinx.path)
+    return {
+        'yuzu': {
+            'path': str(yuzu_path),
+            'path_valid': yuzu_path.is_dir(),
+            'installed': get_yuzu_exe_path().exists(),
+            'version': config.yuzu.yuzu_version,
+            'branch': config.yuzu.branch,
+            'firmware': config.yuzu.yuzu_firmware,
+            'keys_ready': get_prod_keys_path('yuzu').exists(),
+        },
+        'ryujinx': {
+            'path': str(ryujinx_path),
+            'path_valid': ryujinx_path.is_dir(),
+            'installed': get_ryujinx_exe_path() is not None,
+            'version': config.ryujinx.version,
+            'branch': config.ryujinx.branch,
+            'firmware': config.ryujinx.firmware,
+            'keys_ready': get_prod_keys_path('ryujinx').exists(),
+        },
+    }
+
+
+def _check_required_files(emu_path: Path, required_files):
+    missing = []
+    for candidates in required_files:
+        if not any(emu_path.joinpath(name).exists() for name in candidates):
+            missing.append(' / '.join(candidates))
+    return missing
+
+
+def verify_installation(emu_type: str):
+    if emu_type == 'yuzu':
+        emu_path = Path(config.yuzu.yuzu_path)
+        required_files = [('yuzu.exe',), ('Qt5Core.dll', 'Qt6Core.dll')]
+    else:
+        emu_path = Path(config.ryujinx.path)
+        required_files = [('Ryujinx.Ava.exe', 'Ryujinx.exe'), ('SDL2.dll',)]
+    missing = _check_required_files(emu_path, required_files) if emu_path.is_dir() else [str(emu_path)]
+    corrupted = []
+    if emu_path.is_dir():
+        for file in emu_path.iterdir():
+            if file.is_file() and file.suffix.lower() in ('.exe', '.dll') and file.stat().st_size == 0:
+                corrupted.append(file.name)
+    res = {
+        'path': str(emu_path.absolute()),
+        'ok': not missing and not corrupted,
+        'missing': missing,
+        'corrupted': corrupted,
+    }
+    logger.info(f'{emu_type} installation verify result: {res}')
+    if not res['ok']:
+        from utils.i18n import tr, get_emu_name
+        send_notify(tr('verify.incomplete', emu=get_emu_name(emu_type), missing=missing, corrupted=corrupted))
+    return res
+
+
+def rename_to_backup_folder(folder: Path):
+    import time
+    backup_folder = folder.with_name(f'{folder.name}_backup_{int(time.time() * 1000)}')
+    logger.info(f'rename {folder} to {backup_folder}')
+    send_notify(f'已将 {folder} 重命名为 {backup_folder}')
+    folder.rename(backup_folder)
+    return backup_folder
+
+
+def migrate_user_folder(src: Path, dst: Path):
+    if not src.exists():
+        logger.info(f'{src} not exist, skip migrate.')
+        return
+    if dst.exists() and any(dst.iterdir()):
+        rename_to_backup_folder(dst)
+    elif dst.exists():
+        dst.rmdir()
+    dst.parent.mkdir(parents=True, exist_ok=True)
+    logger.info(f'migrating {src} to {dst}')
+    send_notify(f'正在迁移 {src} 至 {dst}...')
+    shutil.move(str(src), str(dst))
+
+
+emulator_executables = {
+    'yuzu': ('yuzu.exe', 'yuzu-cmd.exe'),
+    'ryujinx': ('Ryujinx.exe', 'Ryujinx.Ava.exe'),
+    'cemu': ('Cemu.exe',),
+    'citron': ('citron.exe', 'citron-cmd.exe'),
+    'suyu': ('suyu.exe', 'suyu-cmd.exe'),
+}
+
+
+def detect_emulator_conflicts(target_dir: Path, emu_type: str):
+    """
+    find executables of other emulators in the install folder
+    :return: list of {'emulator', 'file'}
+    """
+    if not target_dir.is_dir():
+        return []
+    file_names = {p.name.lower(): p.name for p in target_dir.iterdir() if p.is_file()}
+    res = []
+    for emulator, exe_names in emulator_executables.items():
+        if emulator == emu_type:
+            continue
+        for exe_name in exe_names:
+            if exe_name.lower() in file_names:
+                res.append({'emulator': emulator, 'file': file_names[exe_name.lower()]})
+    return res
+
+
+def check_emulator_conflicts(target_dir: Path, emu_type: str, allow_conflict=False):
+    from module.msg_notifier import send_notify_leveled
+    conflicts = detect_emulator_conflicts(target_dir, emu_type)
+    if not conflicts:
+        return
+    files = ', '.join(item['file'] for item in conflicts)
+    logger.warning(f'found other emulators in {target_dir}: {conflicts}')
+    if allow_conflict:
+        send_notify_leveled('warning', f'安装目录 {target_dir} 中存在其它模拟器的文件 ({files}), 已确认继续安装.')
+        return
+    send_notify_leveled('warning', f'安装目录 {target_dir} 中存在其它模拟器的文件 ({files}), '
+                                   f'继续安装可能覆盖或删除这些文件.')
+    raise RuntimeError(f'安装目录中存在其它模拟器的文件 ({files}), 请更换安装目录或确认后继续安装.')
+
+
+def is_package_cached(emu_type: str, branch: str, version: str):
+    """
+    check whether the install package of given version is already downloaded
+    :return: path of the package, None if not downloaded or the release asset does not exist
+    """
+    from module.downloader import get_cached_download_path
+    if emu_type == 'yuzu':
+        from module.yuzu import find_yuzu_download_asset
+        asset = find_yuzu_download_asset(version, branch)
+    elif emu_type == 'ryujinx':
+        from module.ryujinx import get_ryujinx_download_asset
+        asset = get_ryujinx_download_asset(version, branch)
+    else:
+        raise RuntimeError(f'不支持的模拟器类型: {emu_type}')
+    if not asset:
+        return None
+    path = get_cached_download_path(asset['name'], asset.get('size'))
+    return str(path.absolute()) if path else None
+
+
+def setup_all(emu_type: str, version: str, firmware_version: str = None, branch: str = None):
+    """
+    install emulator, firmware and check keys in one go
+    :param emu_type: yuzu or ryujinx
+    :param version: emulator version
+    :param firmware_version: firmware version, latest for the newest one, None to skip firmware
+    :param branch: emulator branch, current branch is used if not specified
+    :return: status of each step
+    """
+    from utils.i18n import tr, get_emu_name
+    if emu_type == 'yuzu':
+        from module.yuzu import install_yuzu, install_firmware_to_yuzu
+        branch = branch or config.yuzu.branch
+        install_emu, install_fw = lambda: install_yuzu(version, branch), install_firmware_to_yuzu
+    elif emu_type == 'ryujinx':
+        from module.ryujinx import install_ryujinx_by_version, install_firmware_to_ryujinx
+        branch = branch or config.ryujinx.branch
+        install_emu, install_fw = lambda: install_ryujinx_by_version(version, branch), install_firmware_to_ryujinx
+    else:
+        raise RuntimeError(tr('setup.unsupported_emu', emu=emu_type))
+
+    def install_firmware_step():
+        target_version = firmware_version
+        if target_version == 'latest':
+            target_version = get_firmware_infos()[0]['version']
+        installed_version = install_fw(target_version)
+        if not installed_version:
+            raise RuntimeError(tr('firmware.install_failed', version=target_version))
+        return installed_version
+
+    def check_keys_step():
+        from module.keys import notify_missing_keys
+        status = notify_missing_keys(emu_type)
+        if not status['prod_keys']:
+            raise RuntimeError(tr('setup.prod_keys_missing'))
+
+    # name, function, whether the whole setup fails if the step fails
+    steps = [
+        (tr('setup.step_install_emu', emu=get_emu_name(emu_type), branch=branch, version=version), install_emu, True),
+        (tr('setup.step_install_firmware', version=firmware_version),
+         install_firmware_step if firmware_version else None, True),
+        (tr('setup.step_check_keys'), check_keys_step, False),
+    ]
+    res = [{'name': name, 'status': 'pending', 'msg': None} for name, _, _ in steps]
+    for i, (name, func, critical) in enumerate(steps):
+        step = res[i]
+        if func is None:
+            step['status'] = 'skipped'
+            continue
+        send_notify(tr('setup.step_running', index=i + 1, total=len(steps), name=name))
+        step['status'] = 'running'
+        try:
+            func()
+            step['status'] = 'success'
+        except Exception as e:
+            logger.error(f'setup step [{name}] failed, msg: {str(e)}')
+            step['status'], step['msg'] = 'failed', str(e)
+            if critical:
+                for remaining in res[i + 1:]:
+                    remaining['status'] = 'skipped'
+                send_notify(tr('setup.step_aborted', index=i + 1, total=len(steps), name=name, error=str(e)))
+                raise RuntimeError(tr('setup.failed', name=name, error=str(e))) from e
+            send_notify(tr('setup.step_not_passed', index=i + 1, total=len(steps), name=name, error=str(e)))
+    logger.info(f'setup all finished: {res}')
+    send_notify(tr('setup.finished', emu=get_emu_name(emu_type)))
+    return res
+
+
+def check_all_updates():
+    """
+    check updates of this app, installed emulators and firmware concurrently
+    :return: dict of {'current', 'latest', 'has_update', 'error'} by item name
+    """
+    from concurrent.futures import ThreadPoolExecutor
+
+    def check_self():
+        from module.updater import check_update
+        from config import current_version
+        has_update, latest = check_update()
+        return current_version, latest, has_update
+
+    def check_yuzu():
+        from repository.yuzu import get_all_yuzu_release_versions
+        latest = get_all_yuzu_release_versions(config.yuzu.branch, limit=1)[0]
+        return config.yuzu.yuzu_version, latest, None
+
+    def check_ryujinx():
+        from repository.ryujinx import get_latest_ryujinx_release_info
+        latest = get_latest_ryujinx_release_info()['tag_name']
+        return config.ryujinx.version, latest, None
+
+    def check_firmware(current):
+        return lambda: (current, get_firmware_infos()[0]['version'], None)
+
+    checks = {'ns_emu_tools': check_self}
+    if config.yuzu.yuzu_version:
+        checks['yuzu'] = check_yuzu
+    if config.ryujinx.version:
+        checks['ryujinx'] = check_ryujinx
+    if config.yuzu.yuzu_firmware:
+        checks['yuzu_firmware'] = check_firmware(config.yuzu.yuzu_firmware)
+    if config.ryujinx.firmware:
+        checks['ryujinx_firmware'] = check_firmware(config.ryujinx.firmware)
+    res = {}
+    with ThreadPoolExecutor(max_workers=len(checks)) as executor:
+        futures = {name: executor.submit(func) for name, func in checks.items()}
+        for name, future in futures.items():
+            try:
+                current, latest, has_update = future.result()
+                if has_update is None:
+                    has_update = current != latest
+                res[name] = {'current': current, 'latest': latest, 'has_update': has_update, 'error': None}
+            except Exception as e:
+                logger.warning(f'fail to check update of {name}, msg: {str(e)}')
+                res[name] = {'current': None, 'latest': None, 'has_update': False, 'error': str(e)}
+    logger.info(f'updates summary: {res}')
+    return res
+
+
+def preview_emulator_paths(base_path: str, emu_type: str):
+    """
+    compute the key folders of emulator installed in base_path without creating them
+    :return: dict of install, user, keys, nand, load and firmware path
+    """
+    from utils.common import validate_install_path
+    base_path = validate_install_path(base_path)
+    if emu_type == 'yuzu':
+        from module.yuzu import get_yuzu_user_path, get_yuzu_nand_path, get_yuzu_load_path
+        user_path = get_yuzu_user_path(base_path)
+        nand_path = get_yuzu_nand_path(user_path)
+        paths = {
+            'user': user_path,
+            'keys': user_path.joinpath('keys'),
+            'nand': nand_path,
+            'load': get_yuzu_load_path(user_path),
+            'firmware': nand_path.joinpath(r'system\Contents\registered'),
+        }
+    elif emu_type == 'ryujinx':
+        from module.ryujinx import get_ryujinx_user_folder
+        user_path = get_ryujinx_user_folder(base_path)
+        paths = {
+            'user': user_path,
+            'keys': user_path.joinpath('system'),
+            'nand': user_path.joinpath('bis'),
+            'load': user_path.joinpath('mods/contents'),
+            'firmware': user_path.joinpath(r'bis\system\Contents\registered'),
+        }
+    else:
+        raise RuntimeError(f'不支持的模拟器类型: {emu_type}')
+    return {'install': str(base_path), **{k: str(v.absolute()) for k, v in paths.items()}}
+
+
 if __name__ == '__main__':
     # infos = get_firmware_infos()
     # for info in infos:
@@ -1,40 +1,236 @@
+import re
 import subprocess
 import time
-from typing import Optional
+import threading
+from collections import deque
+from typing import Optional, List, Callable
 import logging
 import aria2p
 from pathlib import Path
 import os
-from module.msg_notifier import send_notify
+from module.msg_notifier import send_notify, send_notify_leveled
+from utils.i18n import tr
+from utils.common import format_bytes, format_eta, popen_hidden
 from config import config
-from utils.network import get_available_port, get_global_options, init_download_options_with_proxy
+from utils.network import get_available_port, get_global_options, init_download_options_with_proxy, \
+    get_remote_file_validator, get_download_source_name, try_switch_mirror, is_proxy_available, \
+    clamp_download_connections
 
 aria2: Optional[aria2p.API] = None
 aria2_process: Optional[subprocess.Popen] = None
+aria2_port: Optional[int] = None
+stall_timeout = 60
+max_stall_retry = 3
+# aria2 exit status of timeout, network problem and name resolution failure
+network_error_codes = {'2', '6', '19'}
 download_path = Path('./download/')
 aria2_path = Path(os.path.realpath(os.path.dirname(__file__))).joinpath('aria2c.exe')
+aria2_static_build_repo = 'abcfy2/aria2-static-build'
+aria2_static_build_arch_map = {
+    'x86_64': 'x86_64',
+    'amd64': 'x86_64',
+    'aarch64': 'aarch64',
+    'arm64': 'aarch64',
+    'armv7l': 'arm',
+    'i686': 'i686',
+}
+aria2_log_path = Path('aria2.log')
+aria2_log_line_re = re.compile(r'^(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2})\.\d+ \[(\w+)] \[[^]]*] (.*)$')
 if not download_path.exists():
     download_path.mkdir()
 logger = logging.getLogger(__name__)
+download_listeners: List[Callable[[dict], None]] = []
+download_listeners_lock = threading.Lock()
+# gid of downloads which have finished transfer and are being verified
+verifying_gids = set()
+# priority of downloads by gid, download with higher priority is placed in front of the aria2 queue
+download_priorities = {}
+# size and duration of recently finished downloads, aria2 does not log transfer speed
+finished_download_stats = deque(maxlen=100)
+firmware_download_priority = 10
+aria2_init_lock = threading.Lock()
+aria2_rpc_ready_timeout = 5
+
+
+def add_download_listener(listener):
+    with download_listeners_lock:
+        if listener not in download_listeners:
+            download_listeners.append(listener)
+
+
+def remove_download_listener(listener):
+    with download_listeners_lock:
+        if listener in download_listeners:
+            download_listeners.remove(listener)
+
+
+def publish_download_event(event_type: str, info=None, **kwargs):
+    event = {'type': event_type}
+    if info is not None:
+        event.update({
+            'gid': info.gid,
+            'name': info.name,
+            'status': info.status,
+            'completed_length': info.completed_length,
+            'total_length': info.total_length,
+            'download_speed': info.download_speed,
+        })
+    event.update(kwargs)
+    with download_listeners_lock:
+        listeners = list(download_listeners)
+    for listener in listeners:
+        try:
+            listener(event)
+        except Exception as e:
+            logger.warning(f'error occur in download listener {listener}, msg: {str(e)}')
+
+
+def get_aria2_static_asset_name():
+    """
+    asset name of the static aria2 build for current platform, None if there is no build for it
+    """
+    import platform
+    if platform.system() != 'Linux':
+        # aria2-static-build only publishes linux builds, macOS users need to install aria2 by homebrew
+        logger.info(f'no static aria2 build available for {platform.system()}')
+        return None
+    arch = aria2_static_build_arch_map.get(platform.machine().lower())
+    return f'aria2-{arch}-linux-musl_static.zip' if arch else None
+
+
+def download_aria2_binary(target_path: Path):
+    asset_name = get_aria2_static_asset_name()
+    if not asset_name:
+        return None
+    from utils.network import request_github_api, get_github_download_url, stream_download
+    release = request_github_api(f'https://api.github.com/repos/{aria2_static_build_repo}/releases/latest')
+    asset = next((a for a in release.get('assets', []) if a['name'] == asset_name), None)
+    if not asset:
+        logger.warning(f'asset {asset_name} not found in release {release.get("tag_name")}')
+        return None
+    import tempfile
+    import zipfile
+    import stat
+    send_notify(f'正在下载 aria2 ({asset_name})...')
+    with tempfile.TemporaryDirectory() as tmp_dir:
+        zip_path = Path(tmp_dir).joinpath(asset_name)
+        stream_download(get_github_download_url(asset['browser_download_url']), zip_path)
+        with zipfile.ZipFile(zip_path) as zf:
+            member = next((name for name in zf.namelist() if Path(name).name == 'aria2c'), None)
+            if not member:
+                logger.warning(f'aria2c not found in {asset_name}')
+                return None
+            target_path.parent.mkdir(parents=True, exist_ok=True)
+            target_path.write_bytes(zf.read(member))
+    target_path.chmod(target_path.stat().st_mode | stat.S_IXUSR | stat.S_IXGRP | stat.S_IXOTH)
+    logger.info(f'aria2c downloaded to {target_path}')
+    return target_path
+
+
+def get_aria2_path():
+    if os.name == 'nt':
+        return aria2_path
+    bundled_path = aria2_path.with_name('aria2c')
+    if bundled_path.exists():
+        import stat
+        bundled_path.chmod(bundled_path.stat().st_mode | stat.S_IXUSR | stat.S_IXGRP | stat.S_IXOTH)
+        return bundled_path
+    import shutil
+    system_path = shutil.which('aria2c')
+    if system_path:
+        return Path(system_path)
+    try:
+        downloaded_path = download_aria2_binary(bundled_path)
+        if downloaded_path:
+            return downloaded_path
+    except Exception as e:
+        logger.error(f'fail to download aria2c, msg: {str(e)}')
+    logger.error('aria2c not found.')
+    send_notify('未找到 aria2c, 请通过系统的包管理器安装 aria2, 例如 apt install aria2 或 brew install aria2')
+    raise RuntimeError('aria2c not found')
+
+
+def get_default_download_dir():
+    save_dir = config.setting.download.saveDir
+    if not save_dir:
+        return download_path
+    path = Path(save_dir)
+    try:
+        path.mkdir(parents=True, exist_ok=True)
+        if path.is_dir() and os.access(path, os.W_OK):
+            return path
+    except OSError as e:
+        logger.warning(f'fail to create download dir {path}, reason: {e}')
+    logger.warning(f'download dir {path} is not available, fallback to {download_path.absolute()}')
+    send_notify_leveled('warning', f'下载目录 {path} 无法使用, 将使用默认目录 {download_path.absolute()}')
+    return download_path
+
+
+def get_cached_download_path(file_name: str, expected_size=None):
+    """
+    find completely downloaded file in download folder
+    :return: path of the file, None if not found or incomplete
+    """
+    path = get_default_download_dir().joinpath(file_name)
+    if not path.is_file() or path.with_name(file_name + '.aria2').exists():
+        return None
+    if expected_size and path.stat().st_size != expected_size:
+        logger.info(f'size of cached file {path} mismatch, expected: {expected_size}, '
+                    f'actual: {path.stat().st_size}')
+        return None
+    return path
 
 
 def init_aria2():
+    with aria2_init_lock:
+        if aria2:
+            return
+        try:
+            _start_aria2()
+        except Exception:
+            _reset_aria2()
+            raise
+
+
+def _reset_aria2():
     global aria2
     global aria2_process
-    if aria2:
-        return
+    if aria2_process and aria2_process.poll() is None:
+        aria2_process.kill()
+    aria2 = None
+    aria2_process = None
+
+
+def _wait_aria2_rpc_ready(api: aria2p.API):
+    deadline = time.time() + aria2_rpc_ready_timeout
+    while True:
+        if aria2_process.poll() is not None:
+            raise RuntimeError(f'aria2 进程异常退出, 退出码: {aria2_process.returncode}')
+        try:
+            api.client.get_version()
+            return
+        except Exception as e:
+            if time.time() > deadline:
+                raise RuntimeError(f'aria2 rpc 服务启动超时: {e}')
+        time.sleep(0.2)
+
+
+def _start_aria2():
+    global aria2
+    global aria2_process
+    global aria2_port
     port = get_available_port()
+    aria2_port = port
     send_notify(f'starting aria2 daemon at port {port}')
     logger.info(f'starting aria2 daemon at port {port}')
-    st_inf = subprocess.STARTUPINFO()
-    st_inf.dwFlags = st_inf.dwFlags | subprocess.STARTF_USESHOWWINDOW
-    cli = [aria2_path, '--enable-rpc', '--rpc-listen-port', str(port),
-           '--rpc-secret', '123456', '--log', 'aria2.log', '--log-level=info']
+    cli = [get_aria2_path(), '--enable-rpc', '--rpc-listen-port', str(port),
+           '--rpc-secret', '123456', '--log', str(aria2_log_path), '--log-level=info',
+           '--dir', str(get_default_download_dir().absolute())]
     if config.setting.download.disableAria2Ipv6:
         cli.append('--disable-ipv6=true')
     logger.info(f'aria2 cli: {cli}')
-    aria2_process = subprocess.Popen(cli, stdout=subprocess.DEVNULL, stderr=subprocess.STDOUT, startupinfo=st_inf)
-    aria2 = aria2p.API(
+    aria2_process = popen_hidden(cli, stdout=subprocess.DEVNULL, stderr=subprocess.STDOUT)
+    api = aria2p.API(
         aria2p.Client(
             host="http://127.0.0.1",
             port=port,
@@ -42,37 +238,265 @@ def init_aria2():
             timeout=10
         )
     )
+    _wait_aria2_rpc_ready(api)
+    aria2 = api
+    apply_global_options()
+    import atexit
+    atexit.unregister(shutdown_aria2)
+    atexit.register(shutdown_aria2)
+
+
+def apply_global_options():
+    if not aria2:
+        return
     global_options = get_global_options()
     logger.info(f'aria2 global options: {global_options}')
     aria2.set_global_options(global_options)
-    import atexit
-    atexit.register(shutdown_aria2)
 
 
-def download(url, save_dir=None, options=None, download_in_background=False):
+def check_resume_consistency(url, save_dir, options, headers=None):
+    from urllib.parse import urlparse, unquote
+    name = options.get('out') or unquote(urlparse(url).path.split('/')[-1])
+    if not name:
+        return
+    target_file = Path(save_dir).joinpath(name)
+    control_file = target_file.with_name(name + '.aria2')
+    validator_file = target_file.with_name(name + '.validator')
+    validator = get_remote_file_validator(url, headers)
+    if control_file.exists() and validator_file.exists() and validator \
+            and validator_file.read_text(encoding='utf-8') != validator:
+        logger.info(f'remote file of {url} has been changed, drop partial file: {target_file}')
+        send_notify('服务器上的文件已变更, 放弃续传并重新下载')
+        for file in (target_file, control_file):
+            if file.exists():
+                os.remove(file)
+    if validator:
+        validator_file.write_text(validator, encoding='utf-8')
+
+
+def remove_validator_file(file_path):
+    validator_file = Path(f'{file_path}.validator')
+    if validator_file.exists():
+        os.remove(validator_file)
+
+
+def release_stopped_download(info):
+    """
+    clean up sidecar files and queue priority of a download which is complete, removed or failed
+    """
+    download_priorities.pop(info.gid, None)
+    file_path = info.files[0].path if info.files else None
+    if not file_path or not Path(file_path).name:
+        return
+    if info.has_failed and info.error_code != '13' and Path(f'{file_path}.aria2').exists():
+        # partial file is kept for resuming, the validator is needed to check it next time
+        return
+    remove_validator_file(file_path)
+
+
+def release_stopped_downloads():
+    # background downloads are not waited by anyone, so their stopped state is picked up here
+    for info in aria2.get_downloads():
+        if info.is_active or info.is_waiting or info.is_paused or info.gid in verifying_gids:
+            continue
+        release_stopped_download(info)
+
+
+def purge_stopped_downloads():
+    release_stopped_downloads()
+    aria2.autopurge()
+
+
+def get_queue_position(priority: int):
+    if not priority:
+        return None
+    position = 0
+    for info in aria2.get_downloads():
+        if not info.is_waiting:
+            continue
+        if download_priorities.get(info.gid, 0) < priority:
+            break
+        position += 1
+    return position
+
+
+def add_download_with_priority(url, options, priority=0):
+    info = aria2.add_uris([url], options=options, position=get_queue_position(priority))
+    if priority:
+        download_priorities[info.gid] = priority
+    return info
+
+
+def check_download_proxy(options):
+    proxy = options.get('all-proxy')
+    if not proxy or is_proxy_available(proxy):
+        return
+    if config.setting.download.fallbackDirectOnProxyError:
+        logger.warning(f'proxy {proxy} is not available, fallback to direct connection.')
+        send_notify_leveled('warning', f'代理 {proxy} 不可用, 将尝试直连下载')
+        options.pop('all-proxy')
+        return
+    send_notify_leveled('error', f'代理 {proxy} 不可用, 请检查代理软件是否正常运行')
+    raise RuntimeError(f'代理不可用: {proxy}')
+
+
+def download(url, save_dir=None, options=None, download_in_background=False, headers=None, priority=0):
     init_aria2()
+    origin_options = options
     tmp = init_download_options_with_proxy()
     tmp['auto-file-renaming'] = 'false'
     tmp['allow-overwrite'] = 'false'
     if options is not None:
         tmp.update(options)
     options = tmp
+    if headers:
+        options['header'] = [f'{k}: {v}' for k, v in headers.items()]
+    check_download_proxy(options)
+    clamp_download_connections(url, options)
     if save_dir is not None:
         options['dir'] = save_dir
     else:
-        options['dir'] = str(download_path)
-    info = aria2.add_uris([url], options=options)
+        options['dir'] = str(get_default_download_dir())
+    check_resume_consistency(url, options['dir'], options, headers)
+    start_time = time.time()
+    source = get_download_source_name(url)
+    logger.info(f'download {url} from source: {source}')
+    send_notify(tr('download.start', source=source))
     if download_in_background:
+        info = add_download_with_priority(url, options, priority)
+        publish_download_event('added', info, url=url, source=source)
         return info
+    info = add_and_wait_download(url, options, source, priority)
+    network_wait_deadline = time.time() + (config.setting.download.networkRecoveryTimeout or 0)
+    while info.error_code in network_error_codes and wait_for_network_recovery(
+            url, network_wait_deadline, options.get('all-proxy')):
+        logger.info(f'network recovered, resume download of {url}')
+        send_notify(tr('download.network_recovered'))
+        aria2.remove([info], clean=False)
+        download_priorities.pop(info.gid, None)
+        info = add_and_wait_download(url, options, source, priority)
+    print('\r')
+    download_priorities.pop(info.gid, None)
+    if info.is_removed:
+        publish_download_event('removed', info, url=url, source=source)
+        logger.info(f'download [{info.gid}] has been removed.')
+        send_notify(tr('download.cancelled'))
+        release_stopped_download(info)
+        raise RuntimeError(tr('download.cancelled'))
+    if info.error_code != '0':
+        if info.error_code == '13':
+            logger.info('file already exist.')
+            send_notify(tr('download.file_exists'))
+            remove_validator_file(info.files[0].path)
+        else:
+            publish_download_event('error', info, url=url, source=source, error_code=info.error_code,
+                                   error_message=info.error_message)
+            logger.error(f'info.error_code: {info.error_code}, error message: {info.error_message}')
+            next_url = try_switch_mirror(url)
+            if next_url:
+                send_notify(tr('download.switch_mirror', source=source, next_source=get_download_source_name(next_url)))
+                aria2.remove([info], clean=True)
+                return download(next_url, save_dir, origin_options, download_in_background, headers, priority)
+            release_stopped_download(info)
+            raise RuntimeError(tr('download.failed', code=info.error_code, message=info.error_message))
+    else:
+        logger.info(f'progress: {info.progress_string()}, total size: {info.total_length_string()}')
+        verifying_gids.add(info.gid)
+        publish_download_event('verifying', info, url=url, source=source, status='verifying')
+        try:
+            verify_downloaded_file(info)
+        finally:
+            verifying_gids.discard(info.gid)
+        remove_validator_file(info.files[0].path)
+    duration_ms = int((time.time() - start_time) * 1000)
+    finished_download_stats.append({'path': str(info.files[0].path), 'size': info.total_length,
+                                    'duration_ms': duration_ms})
+    logger.info(f'download finished, path: {info.files[0].path}, size: {info.total_length}, '
+                f'duration: {duration_ms / 1000:.1f}s')
+    send_notify(tr('download.finished', path=info.files[0].path, size=format_bytes(info.total_length),
+                   duration=format_eta(duration_ms / 1000)))
+    publish_download_event('complete', info, url=url, source=source, path=str(info.files[0].path),
+                           size=info.total_length, duration_ms=duration_ms)
+    purge_stopped_downloads()
+    return info
+
+
+def verify_downloaded_file(info):
+    file_path = Path(info.files[0].path)
+    if not file_path.exists():
+        logger.error(f'download [{info.gid}] is complete but file not found: {file_path}')
+        raise RuntimeError(tr('download.file_missing', path=file_path))
+    actual_size = file_path.stat().st_size
+    if info.total_length and actual_size != info.total_length:
+        logger.error(f'size of {file_path} mismatch, expected: {info.total_length}, actual: {actual_size}')
+        raise RuntimeError(tr('download.size_mismatch', expected=format_bytes(info.total_length),
+                              actual=format_bytes(actual_size)))
+
+
+def add_and_wait_download(url, options, source=None, priority=0):
+    for stall_retry_count in range(max_stall_retry + 1):
+        info = add_download_with_priority(url, options, priority)
+        publish_download_event('added', info, url=url, source=source)
+        info, stalled = wait_download(info, source)
+        if not stalled:
+            return info
+        download_priorities.pop(info.gid, None)
+        if stall_retry_count < max_stall_retry:
+            send_notify(tr('download.stall_retry', count=stall_retry_count + 1, total=max_stall_retry))
+    logger.error(f'download of {url} stalled after {max_stall_retry} retries.')
+    raise RuntimeError(tr('download.stalled'))
+
+
+def is_network_available(url, proxy=None):
+    # probe the same way aria2 reaches the host, otherwise a blocked direct route hides a working proxy
+    import requests
+    from utils.network import is_proxy_available
+    if proxy and not is_proxy_available(proxy):
+        return False
+    proxies = {'http': proxy, 'https': proxy} if proxy else None
+    try:
+        requests.head(url, proxies=proxies, timeout=5, allow_redirects=False)
+        return True
+    except requests.RequestException as e:
+        logger.debug(f'network probe of {url} failed, msg: {str(e)}')
+        return False
+
+
+def wait_for_network_recovery(url, deadline, proxy=None):
+    if time.time() >= deadline:
+        return False
+    logger.warning(f'download of {url} failed by network error, waiting for network recovery.')
+    send_notify(tr('download.network_waiting'))
+    while time.time() < deadline:
+        if is_network_available(url, proxy):
+            return True
+        time.sleep(5)
+    logger.error(f'network is not recovered before deadline.')
+    send_notify(tr('download.network_timeout'))
+    return False
+
+
+def wait_download(info, source=None):
     info = aria2.get_download(info.gid)
     retry_count = 0
-    while info.is_active:
+    last_completed_length = info.completed_length
+    last_progress_time = time.time()
+    while info.is_active or info.is_waiting or info.is_paused:
         print(f'\rprogress: {info.progress_string()}, '
                     f'connections: {info.connections}, '
                     f'{info.completed_length_string()}/{info.total_length_string()} , '
                     f'download speed: {info.download_speed_string()}, eta: {info.eta_string()}', end='')
         send_notify(f'下载速度: {info.download_speed_string()}, '
                     f'{info.completed_length_string()}/{info.total_length_string()}')
+        publish_download_event('progress', info, source=source)
+        if info.completed_length != last_completed_length or not info.is_active:
+            last_completed_length = info.completed_length
+            last_progress_time = time.time()
+        elif time.time() - last_progress_time > stall_timeout:
+            logger.warning(f'download [{info.gid}] has no progress in {stall_timeout} seconds, removing it.')
+            aria2.remove([info], force=True)
+            publish_download_event('stalled', info, source=source)
+            return info, True
         time.sleep(0.3)
         try:
             info = aria2.get_download(info.gid)
@@ -80,19 +504,185 @@ def download(url, save_dir=None, options=None, download_in_background=False):
             retry_count += 1
             if retry_count > 15:
                 raise e
-    print('\r')
-    if info.error_code != '0':
-        if info.error_code == '13':
-            logger.info('file already exist.')
-            send_notify('文件已存在, 跳过下载.')
-        else:
-            logger.error(f'info.error_code: {info.error_code}, error message: {info.error_message}')
-            raise RuntimeError(f'下载出错, error_code: {info.error_code}, error message: {info.error_message}')
-    else:
-        logger.info(f'progress: {info.progress_string()}, total size: {info.total_length_string()}')
-    send_notify('下载完成')
-    aria2.autopurge()
-    return info
+    return info, False
+
+
+def _get_download_info_source(info):
+    try:
+        return get_download_source_name(info.files[0].uris[0]['uri'])
+    except (IndexError, KeyError, AttributeError):
+        return None
+
+
+def list_downloads():
+    init_aria2()
+    release_stopped_downloads()
+    res = []
+    for info in aria2.get_downloads():
+        res.append({
+            'gid': info.gid,
+            'name': info.name,
+            'status': 'verifying' if info.gid in verifying_gids else info.status,
+            'progress': info.progress_string(),
+            'source': _get_download_info_source(info),
+        })
+    return res
+
+
+def pause_download(gid: str):
+    init_aria2()
+    info = aria2.get_download(gid)
+    logger.info(f'pause download [{gid}]')
+    return aria2.pause([info])[0]
+
+
+def resume_download(gid: str):
+    init_aria2()
+    info = aria2.get_download(gid)
+    logger.info(f'resume download [{gid}]')
+    return aria2.resume([info])[0]
+
+
+def cancel_download(gid: str):
+    init_aria2()
+    info = aria2.get_download(gid)
+    logger.info(f'cancel download [{gid}]')
+    res = aria2.remove([info], force=True)[0]
+    download_priorities.pop(gid, None)
+    return res
+
+
+def wait_downloads_stopped(gids, timeout=10):
+    """
+    wait until aria2 has released files of the given downloads, removed gids may be purged already
+    """
+    deadline = time.time() + timeout
+    pending = set(gids)
+    while pending and time.time() < deadline:
+        for gid in list(pending):
+            try:
+                info = aria2.get_download(gid)
+            except Exception:
+                pending.discard(gid)
+                continue
+            if not (info.is_active or info.is_waiting or info.is_paused):
+                pending.discard(gid)
+        if pending:
+            time.sleep(0.2)
+    if pending:
+        logger.warning(f'downloads {pending} are not stopped in {timeout} seconds.')
+    return not pending
+
+
+def cancel_all_downloads(remove_files=False):
+    """
+    cancel all unfinished downloads
+    :param remove_files: also remove partial files, aria2 control files and validator files
+    :return: paths of removed files
+    """
+    init_aria2()
+    downloads = [info for info in aria2.get_downloads() if info.is_active or info.is_waiting or info.is_paused]
+    logger.info(f'cancel all downloads: {[info.gid for info in downloads]}, remove_files: {remove_files}')
+    if not downloads:
+        return []
+    file_paths = [Path(file.path) for info in downloads for file in info.files if Path(file.path).name]
+    aria2.remove(downloads, force=True)
+    for info in downloads:
+        download_priorities.pop(info.gid, None)
+    removed_files = []
+    if remove_files:
+        wait_downloads_stopped([info.gid for info in downloads])
+        for file_path in file_paths:
+            for path in (file_path, Path(f'{file_path}.aria2'), Path(f'{file_path}.validator')):
+                try:
+                    if path.is_file():
+                        os.remove(path)
+                        removed_files.append(str(path))
+                except OSError as e:
+                    logger.warning(f'fail to remove {path}, msg: {str(e)}')
+    send_notify(f'已取消 {len(downloads)} 个下载任务')
+    return removed_files
+
+
+def restart_aria2(force=False):
+    global aria2
+    global aria2_process
+    if aria2 and not force:
+        try:
+            active_downloads = [info for info in aria2.get_downloads()
+                                if info.is_active or info.is_waiting or info.is_paused]
+        except Exception as e:
+            logger.warning(f'fail to get downloads from aria2, msg: {str(e)}')
+            active_downloads = []
+        if active_downloads:
+            raise RuntimeError(f'当前有 {len(active_downloads)} 个下载任务未完成, 请等待完成或取消后再重启 aria2.')
+    send_notify('正在重启 aria2...')
+    logger.info('restarting aria2...')
+    shutdown_aria2()
+    if aria2_process:
+        try:
+            aria2_process.wait(timeout=5)
+        except subprocess.TimeoutExpired:
+            logger.warning('aria2 process does not exit in 5 seconds.')
+    aria2 = None
+    aria2_process = None
+    init_aria2()
+    return aria2_port
+
+
+def summarize_aria2_log(max_bytes=2 * 1024 * 1024, max_messages=20):
+    res = {
+        'log_path': str(aria2_log_path.absolute()),
+        'error_count': 0,
+        'warning_count': 0,
+        'completed': [],
+        'errors': [],
+        'warnings': [],
+        'start_time': None,
+        'end_time': None,
+        'average_speed': None,
+        'average_speed_string': None,
+    }
+    stats = list(finished_download_stats)
+    total_duration_ms = sum(stat['duration_ms'] for stat in stats)
+    if total_duration_ms > 0:
+        res['average_speed'] = int(sum(stat['size'] or 0 for stat in stats) * 1000 / total_duration_ms)
+        res['average_speed_string'] = f'{format_bytes(res["average_speed"])}/s'
+    if not aria2_log_path.exists():
+        return res
+    with open(aria2_log_path, 'rb') as f:
+        f.seek(max(0, aria2_log_path.stat().st_size - max_bytes))
+        lines = f.read().decode('utf-8', errors='ignore').splitlines()
+    stats_by_path = {stat['path']: stat for stat in stats}
+    last_entry = None
+    for line in lines:
+        m = aria2_log_line_re.match(line)
+        if not m:
+            # aria2 writes the error reason in the following indented lines
+            if last_entry and line.strip().startswith('->'):
+                last_entry['message'] += '\n' + line.strip()
+            continue
+        log_time, level, msg = m.groups()
+        res['start_time'] = res['start_time'] or log_time
+        res['end_time'] = log_time
+        last_entry = None
+        if level == 'ERROR':
+            res['error_count'] += 1
+            last_entry = {'time': log_time, 'message': msg}
+            res['errors'].append(last_entry)
+        elif level == 'WARN':
+            res['warning_count'] += 1
+            last_entry = {'time': log_time, 'message': msg}
+            res['warnings'].append(last_entry)
+        elif 'Download complete: ' in msg:
+            path = msg.split('Download complete: ', 1)[1]
+            stat = stats_by_path.get(path)
+            speed = int((stat['size'] or 0) * 1000 / stat['duration_ms']) if stat and stat['duration_ms'] else None
+            res['completed'].append({'time': log_time, 'path': path, 'speed': speed})
+    res['errors'] = res['errors'][-max_messages:]
+    res['warnings'] = res['warnings'][-max_messages:]
+    res['completed'] = res['completed'][-max_messages:]
+    return res
 
 
 def shutdown_aria2():
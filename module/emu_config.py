@@ -0,0 +1,126 @@
+import shutil
+import time
+from pathlib import Path
+import logging
+from module.msg_notifier import send_notify
+
+logger = logging.getLogger(__name__)
+max_emulator_config_backups = 10
+
+
+def get_emulator_config_path(emu_type: str):
+    if emu_type == 'yuzu':
+        from module.yuzu import get_yuzu_user_path
+        return get_yuzu_user_path().joinpath('config/qt-config.ini')
+    else:
+        from module.ryujinx import get_ryujinx_user_folder
+        return get_ryujinx_user_folder().joinpath('Config.json')
+
+
+def get_emulator_config_backup_folder(emu_type: str):
+    return get_emulator_config_path(emu_type).parent.joinpath('config_backup')
+
+
+def backup_emulator_config(emu_type: str):
+    config_path = get_emulator_config_path(emu_type)
+    if not config_path.exists():
+        logger.info(f'{emu_type} config not found in path: {config_path}, skip backup.')
+        return None
+    backup_folder = get_emulator_config_backup_folder(emu_type)
+    backup_folder.mkdir(parents=True, exist_ok=True)
+    backup_file = backup_folder.joinpath(f'{config_path.stem}_{int(time.time() * 1000)}{config_path.suffix}')
+    shutil.copy2(config_path, backup_file)
+    logger.info(f'backup {config_path} to {backup_file}')
+    send_notify(f'{emu_type} 配置文件已备份至 {backup_file}')
+    prune_emulator_config_backups(emu_type)
+    return str(backup_file.absolute())
+
+
+def prune_emulator_config_backups(emu_type: str, keep=max_emulator_config_backups):
+    for backup in list_emulator_config_backups(emu_type)[keep:]:
+        logger.info(f'remove old config backup: {backup["path"]}')
+        Path(backup['path']).unlink(missing_ok=True)
+
+
+def list_emulator_config_backups(emu_type: str):
+    config_path = get_emulator_config_path(emu_type)
+    backup_folder = get_emulator_config_backup_folder(emu_type)
+    res = []
+    if not backup_folder.exists():
+        return res
+    for backup_file in sorted(backup_folder.glob(f'{config_path.stem}_*{config_path.suffix}'), reverse=True):
+        res.append({
+            'path': str(backup_file.absolute()),
+            'name': backup_file.name
+        })
+    return res
+
+
+def restore_emulator_config(emu_type: str, backup_file_path: str):
+    backup_file = Path(backup_file_path)
+    if not backup_file.exists():
+        raise RuntimeError(f'文件 {backup_file} 不存在.')
+    config_path = get_emulator_config_path(emu_type)
+    data = backup_file.read_bytes()
+    backup_emulator_config(emu_type)
+    config_path.parent.mkdir(parents=True, exist_ok=True)
+    config_path.write_bytes(data)
+    logger.info(f'restore {emu_type} config from {backup_file}')
+    send_notify(f'已从 {backup_file} 还原 {emu_type} 配置文件')
+
+
+yuzu_backend_map = {'0': 'OpenGL', '1': 'Vulkan', '2': 'Null'}
+yuzu_resolution_map = {'0': '0.5x', '1': '0.75x', '2': '1x', '3': '2x', '4': '3x', '5': '4x', '6': '5x', '7': '6x'}
+yuzu_vsync_mode_map = {'0': 'Immediate', '1': 'Mailbox', '2': 'FIFO', '3': 'FIFO Relaxed'}
+
+
+def _get_ini_value(section, key):
+    if section is None:
+        return None
+    value = section.get(key)
+    if value is None:
+        return None
+    return value.strip().strip('"')
+
+
+def read_yuzu_settings_summary():
+    config_path = get_emulator_config_path('yuzu')
+    if not config_path.exists():
+        raise RuntimeError(f'未找到 yuzu 配置文件: {config_path}')
+    import configparser
+    yuzu_qt_config = configparser.ConfigParser(interpolation=None, strict=False)
+    yuzu_qt_config.read(str(config_path.absolute()), encoding='utf-8')
+    renderer = yuzu_qt_config['Renderer'] if yuzu_qt_config.has_section('Renderer') else None
+    system = yuzu_qt_config['System'] if yuzu_qt_config.has_section('System') else None
+    backend = _get_ini_value(renderer, 'backend')
+    resolution = _get_ini_value(renderer, 'resolution_setup')
+    vsync_mode = _get_ini_value(renderer, 'vsync_mode')
+    use_vsync = _get_ini_value(renderer, 'use_vsync')
+    docked_mode = _get_ini_value(system, 'use_docked_mode')
+    return {
+        'config_path': str(config_path.absolute()),
+        'gpu_backend': yuzu_backend_map.get(backend, backend),
+        'resolution': yuzu_resolution_map.get(resolution, resolution),
+        'vsync': yuzu_vsync_mode_map.get(vsync_mode, vsync_mode) if vsync_mode is not None
+        else (use_vsync == 'true' if use_vsync is not None else None),
+        'docked_mode': docked_mode == 'true' if docked_mode is not None else None,
+    }
+
+
+def read_ryujinx_settings_summary():
+    config_path = get_emulator_config_path('ryujinx')
+    if not config_path.exists():
+        raise RuntimeError(f'未找到 Ryujinx 配置文件: {config_path}')
+    import json
+    with open(config_path, 'r', encoding='utf-8') as f:
+        ryujinx_config = json.load(f)
+    res_scale = ryujinx_config.get('res_scale')
+    if res_scale == -1:
+        res_scale = ryujinx_config.get('res_scale_custom')
+    return {
+        'config_path': str(config_path.absolute()),
+        'gpu_backend': ryujinx_config.get('graphics_backend'),
+        'resolution': f'{res_scale}x' if res_scale is not None else None,
+        'vsync': ryujinx_config.get('enable_vsync'),
+        'docked_mode': ryujinx_config.get('docked_mode'),
+    }
@@ -4,64 +4,157 @@ from pathlib import Path
 import logging
 from config import config, dump_config
 import shutil
-from module.msg_notifier import send_notify
+from module.msg_notifier import send_notify, send_notify_leveled
+from utils.common import popen_hidden
 
 logger = logging.getLogger(__name__)
 hactool_path = Path(os.path.realpath(os.path.dirname(__file__))).joinpath('hactool.exe')
 
 
-def detect_firmware_version(emu_type: str):
+def get_prod_keys_path(emu_type: str):
+    from module.keys import get_keys_folder
+    return get_keys_folder(emu_type).joinpath('prod.keys')
+
+
+def get_firmware_path(emu_type: str):
+    if emu_type == 'yuzu':
+        from module.yuzu import get_yuzu_nand_path
+        return get_yuzu_nand_path().joinpath(r'system\Contents\registered')
+    else:
+        from module.ryujinx import get_ryujinx_user_folder
+        return get_ryujinx_user_folder().joinpath(r'bis\system\Contents\registered')
+
+
+def scan_firmware_version(emu_type: str):
     firmware_files = []
     version = None
+    key_path = get_prod_keys_path(emu_type)
+    firmware_path = get_firmware_path(emu_type)
     if emu_type == 'yuzu':
-        from module.yuzu import get_yuzu_nand_path, get_yuzu_user_path
-        firmware_path = get_yuzu_nand_path().joinpath(r'system\Contents\registered')
-        key_path = get_yuzu_user_path().joinpath(r'keys/prod.keys')
         for file in firmware_path.glob('*.nca'):
             if not file.name.endswith('.cnmt.nca'):
                 firmware_files.append(file)
     else:
-        from module.ryujinx import get_ryujinx_user_folder
-        firmware_path = get_ryujinx_user_folder().joinpath(r'bis\system\Contents\registered')
-        key_path = get_ryujinx_user_folder().joinpath(r'system/prod.keys')
         for p in firmware_path.glob('**/00'):
             if p.is_file():
                 firmware_files.append(p)
+    if not firmware_files:
+        logger.info(f'no firmware files found in path: {firmware_path}')
+        send_notify('未能找到相应的固件文件')
+        return None
     if not key_path.exists():
         logger.error(f'prod keys not found in path: {key_path}')
         send_notify('未能找到相应的 prod.keys 文件')
         raise RuntimeError(f'prod keys not found in path: {key_path}')
-    if not firmware_files:
-        logger.error(f'no firmware files found in path: {firmware_path}')
-        send_notify('未能找到相应的固件文件')
-        raise RuntimeError(f'no firmware files found in path: {firmware_path}')
     target_file = find_target_firmware_file(firmware_files, key_path)
     if target_file:
         version = extract_version(target_file, key_path)
+    return version
+
+
+def detect_firmware_version(emu_type: str):
+    version = scan_firmware_version(emu_type)
     if version:
         if emu_type == 'yuzu':
             config.yuzu.yuzu_firmware = version
         else:
             config.ryujinx.firmware = version
         dump_config()
+    else:
+        logger.info(f'unable to detect firmware version of {emu_type}.')
+        send_notify('未能识别已安装的固件版本')
     return version
 
 
+def uninstall_firmware(emu_type: str):
+    firmware_path = get_firmware_path(emu_type)
+    if emu_type == 'yuzu':
+        from module.yuzu import kill_all_yuzu_instance
+        kill_all_yuzu_instance()
+    else:
+        from module.ryujinx import kill_all_ryujinx_instance
+        kill_all_ryujinx_instance()
+    if firmware_path.exists():
+        logger.info(f'removing firmware files in {firmware_path}')
+        send_notify(f'正在清除固件文件: {firmware_path}')
+        shutil.rmtree(firmware_path)
+    firmware_path.mkdir(parents=True, exist_ok=True)
+    if emu_type == 'yuzu':
+        config.yuzu.yuzu_firmware = None
+    else:
+        config.ryujinx.firmware = None
+    dump_config()
+    send_notify('已清除已安装的固件')
+
+
+def verify_installed_firmware_version(emu_type: str, expected_version: str):
+    key_path = get_prod_keys_path(emu_type)
+    if not key_path.exists():
+        logger.info(f'prod keys not found in path: {key_path}, skip firmware version verification.')
+        send_notify('未找到 prod.keys, 跳过固件版本校验')
+        return expected_version
+    send_notify('正在校验固件版本...')
+    actual_version = scan_firmware_version(emu_type)
+    if actual_version and actual_version != expected_version:
+        logger.error(f'firmware version mismatch, expected: {expected_version}, actual: {actual_version}')
+        send_notify_leveled('error', f'安装的固件版本 [{actual_version}] 与目标版本 [{expected_version}] 不一致, '
+                                     f'下载源可能提供了错误的文件')
+        raise RuntimeError(f'firmware version mismatch, expected: {expected_version}, actual: {actual_version}')
+    return actual_version or expected_version
+
+
+def read_nca_info(nca_path, key_path):
+    process = popen_hidden([str(hactool_path), '-k', str(key_path), '-t', 'nca', str(nca_path)],
+                           stdout=subprocess.PIPE, stderr=subprocess.DEVNULL)
+    lines = process.communicate()[0].decode("utf-8", errors='ignore').splitlines()
+    info = {}
+    for line in lines:
+        if ':' not in line:
+            continue
+        key, value = line.split(':', 1)
+        info.setdefault(key.strip(), value.strip())
+    return info
+
+
+def read_title_metadata(nca_path, key_path):
+    """
+    read title id and version from nca file, version is only available in meta (cnmt) nca
+    :param nca_path: path of nca file
+    :param key_path: path of prod.keys
+    :return: dict with title_id, content_type, version and meta_type
+    """
+    info = read_nca_info(nca_path, key_path)
+    res = {
+        'title_id': info.get('Title ID'),
+        'content_type': info.get('Content Type'),
+        'version': None,
+        'meta_type': None,
+    }
+    if res['content_type'] != 'Meta':
+        return res
+    import tempfile
+    import struct
+    with tempfile.TemporaryDirectory() as tmp_dir:
+        process = popen_hidden([str(hactool_path), '-k', str(key_path), '-t', 'nca', str(nca_path),
+                                f'--section0dir={tmp_dir}'],
+                               stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL)
+        process.wait()
+        for cnmt_file in Path(tmp_dir).glob('*.cnmt'):
+            with open(cnmt_file, 'rb') as f:
+                title_id, version, meta_type = struct.unpack('<QIB', f.read(13))
+            res['title_id'] = f'{title_id:016X}'
+            res['version'] = version
+            res['meta_type'] = meta_type
+            break
+    return res
+
+
 def find_target_firmware_file(firmware_files, key_path):
     logger.info(f'scanning firmware files...')
     send_notify('开始扫描固件文件...')
     for file in firmware_files:
-        process = subprocess.Popen(f'"{str(hactool_path)}" -t  keygen -k "{str(key_path)}" -t nca "{str(file)}"',
-                                   stdout=subprocess.PIPE, stderr=subprocess.DEVNULL, shell=True)
-        lines = process.communicate()[0].decode("utf-8").splitlines()
-        title_id = None
-        content_type = None
-        for line in lines:
-            if line.startswith('Title ID:'):
-                title_id = line[9:].strip()
-            elif line.startswith('Content Type:'):
-                content_type = line[13:].strip()
-        if title_id == '0100000000000809' and content_type == 'Data':
+        info = read_nca_info(file, key_path)
+        if info.get('Title ID') == '0100000000000809' and info.get('Content Type') == 'Data':
             logger.info(f'target firmware file: {file}')
             send_notify(f'找到目标固件文件: {file}')
             return file
@@ -72,9 +165,10 @@ def extract_version(target_file, key_path):
     logger.info(f'decrypt file: {target_file}')
     send_notify(f'开始解析目标固件文件: {target_file}')
     tmp_path = Path(tempfile.gettempdir()).joinpath('nst/')
-    process = subprocess.Popen(f'"{str(hactool_path)}" -t  keygen -k "{str(key_path)}" -t nca "{str(target_file)}" '
-                               f'--romfsdir="{str(tmp_path)}"', shell=True,
-                               stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL)
+    version = None
+    process = popen_hidden([str(hactool_path), '-k', str(key_path), '-t', 'nca', str(target_file),
+                            f'--romfsdir={str(tmp_path)}'],
+                           stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL)
     process.wait()
     if tmp_path.joinpath('file').exists():
         with open(tmp_path.joinpath('file'), 'rb') as f:
@@ -82,9 +176,42 @@ def extract_version(target_file, key_path):
             version = f.read(0x18).replace(b'\0', b'').decode()
             logger.info(f'firmware version: {version}')
             send_notify(f'固件版本: {version}')
-    shutil.rmtree(tmp_path)
+    shutil.rmtree(tmp_path, ignore_errors=True)
     return version
 
 
+def get_post_firmware_guidance(emu_type: str):
+    """
+    suggest next steps after firmware installation
+    :return: keys status and list of {'action', 'msg'}
+    """
+    from module.keys import get_keys_status
+    from utils.i18n import tr, get_emu_name
+    if emu_type == 'yuzu':
+        from module.yuzu import get_yuzu_exe_path
+        emu_installed = get_yuzu_exe_path().exists()
+        firmware = config.yuzu.yuzu_firmware
+    else:
+        from module.ryujinx import get_ryujinx_exe_path
+        emu_installed = get_ryujinx_exe_path() is not None
+        firmware = config.ryujinx.firmware
+    keys_status = get_keys_status(emu_type)
+    suggestions = []
+    if not emu_installed:
+        suggestions.append({'action': 'install_emulator',
+                            'msg': tr('guidance.install_emulator', emu=get_emu_name(emu_type))})
+    if not firmware:
+        suggestions.append({'action': 'install_firmware', 'msg': tr('guidance.install_firmware')})
+    if not keys_status['prod_keys']:
+        suggestions.append({'action': 'install_prod_keys',
+                            'msg': tr('guidance.install_prod_keys', path=keys_status['keys_path'])})
+    if not keys_status['title_keys']:
+        suggestions.append({'action': 'install_title_keys',
+                            'msg': tr('guidance.install_title_keys', path=keys_status['keys_path'])})
+    if not suggestions:
+        suggestions.append({'action': 'ready', 'msg': tr('guidance.ready')})
+    return {'keys_status': keys_status, 'suggestions': suggestions}
+
+
 if __name__ == '__main__':
     detect_firmware_version('yuzu')
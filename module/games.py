@@ -0,0 +1,66 @@
+from pathlib import Path
+import logging
+from module.msg_notifier import send_notify
+
+logger = logging.getLogger(__name__)
+meta_type_map = {
+    0x80: 'application',
+    0x81: 'patch',
+    0x82: 'add_on_content',
+}
+
+
+def get_user_contents_path(emu_type: str):
+    if emu_type == 'yuzu':
+        from module.yuzu import get_yuzu_nand_path
+        return get_yuzu_nand_path().joinpath('user/Contents/registered')
+    else:
+        from module.ryujinx import get_ryujinx_user_folder
+        return get_ryujinx_user_folder().joinpath('bis/user/Contents/registered')
+
+
+def get_base_title_id(title_id: str, meta_type: str):
+    tid = int(title_id, 16)
+    if meta_type == 'patch':
+        tid = tid & ~0x800
+    elif meta_type == 'add_on_content':
+        tid = (tid - 0x1000) & ~0xFFF
+    return f'{tid:016X}'
+
+
+def list_meta_files(contents_path: Path):
+    res = []
+    for path in contents_path.glob('**/*.cnmt.nca'):
+        if path.is_file():
+            res.append(path)
+        elif path.joinpath('00').is_file():
+            res.append(path.joinpath('00'))
+    return res
+
+
+def list_installed_games(emu_type: str):
+    from module.firmware import get_prod_keys_path, read_title_metadata
+    from module.cheats import get_game_data
+    contents_path = get_user_contents_path(emu_type)
+    key_path = get_prod_keys_path(emu_type)
+    if not key_path.exists():
+        logger.error(f'prod keys not found in path: {key_path}')
+        send_notify('未能找到相应的 prod.keys 文件')
+        raise RuntimeError(f'prod keys not found in path: {key_path}')
+    logger.info(f'scanning installed games under path: {contents_path}')
+    game_data = get_game_data()
+    res = []
+    for meta_file in list_meta_files(contents_path):
+        metadata = read_title_metadata(meta_file, key_path)
+        meta_type = meta_type_map.get(metadata['meta_type'])
+        if not meta_type or not metadata['title_id']:
+            continue
+        base_title_id = get_base_title_id(metadata['title_id'], meta_type)
+        res.append({
+            'title_id': metadata['title_id'],
+            'base_title_id': base_title_id,
+            'type': meta_type,
+            'version': metadata['version'],
+            'game_name': game_data.get(base_title_id),
+        })
+    return res
@@ -0,0 +1,118 @@
+import time
+import logging
+from contextlib import contextmanager
+from typing import Optional
+from gevent.event import Event
+from module.msg_notifier import send_notify
+from utils.i18n import tr
+
+logger = logging.getLogger(__name__)
+# gevent event so waiting for resume yields to the hub, the webview entry does not patch threading
+install_resume_event = Event()
+install_resume_event.set()
+
+
+def pause_installation():
+    install_resume_event.clear()
+    logger.info('installation will be paused at next checkpoint.')
+    send_notify(tr('install.pause_requested'))
+
+
+def resume_installation():
+    install_resume_event.set()
+    logger.info('installation resumed.')
+
+
+def is_installation_paused():
+    return not install_resume_event.is_set()
+
+
+def wait_if_installation_paused(next_step: str):
+    mark_install_step(next_step)
+    if install_resume_event.is_set():
+        return
+    logger.info(f'installation paused before step: {next_step}')
+    send_notify(tr('install.paused', step=next_step))
+    install_resume_event.wait()
+    send_notify(tr('install.resumed'))
+
+
+class InstallTiming:
+    """
+    step durations of one install flow
+    """
+
+    def __init__(self, flow_name: str, first_step: str):
+        self.flow_name = flow_name
+        self.steps = []
+        self.current_step = (first_step, time.time())
+
+    def mark(self, name: str):
+        self.finish_current_step()
+        self.current_step = (name, time.time())
+
+    def finish_current_step(self):
+        if self.current_step is None:
+            return
+        step_name, start_time = self.current_step
+        self.current_step = None
+        duration_ms = int((time.time() - start_time) * 1000)
+        self.steps.append({'name': step_name, 'duration_ms': duration_ms})
+        logger.info(f'install step [{step_name}] finished in {duration_ms} ms')
+
+    @property
+    def duration_ms(self):
+        return sum(step['duration_ms'] for step in self.steps)
+
+
+active_install_timing: Optional[InstallTiming] = None
+last_install_timing: Optional[InstallTiming] = None
+
+
+def mark_install_step(name: str):
+    """
+    finish current step and start timing of the next step, no-op if no install flow is timing
+    """
+    if active_install_timing is not None:
+        active_install_timing.mark(name)
+
+
+@contextmanager
+def timed_install(flow_name: str, first_step: str):
+    global active_install_timing, last_install_timing
+    timing = InstallTiming(flow_name, first_step)
+    if active_install_timing is not None:
+        # steps keep going to the outer flow, the nested flow only gets its own total
+        logger.warning(f'{flow_name} install is started inside {active_install_timing.flow_name} install, '
+                       f'its steps are not tracked separately.')
+        try:
+            yield timing
+        finally:
+            timing.finish_current_step()
+        return
+    active_install_timing = timing
+    try:
+        yield timing
+    finally:
+        timing.finish_current_step()
+        active_install_timing = None
+        last_install_timing = timing
+        summary = ', '.join(f'{step["name"]}: {step["duration_ms"] / 1000:.1f}s' for step in timing.steps)
+        logger.info(f'{flow_name} install step durations: {summary}')
+
+
+def get_install_step_timings():
+    timing = active_install_timing or last_install_timing
+    return list(timing.steps) if timing else []
+
+
+def build_install_result(path, size, msg: str, duration_ms=0):
+    """
+    structured result of an install flow
+    """
+    return {
+        'path': str(path),
+        'size': size,
+        'duration_ms': duration_ms,
+        'msg': msg,
+    }
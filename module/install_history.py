@@ -0,0 +1,52 @@
+import logging
+from datetime import datetime
+from config import config, dump_config
+
+logger = logging.getLogger(__name__)
+max_install_history = 100
+
+
+def add_install_history(kind: str, emu_type: str, version=None, source=None, **kwargs):
+    """
+    append a record to install history
+    :param kind: firmware or keys
+    :param emu_type: yuzu or ryujinx
+    :param version: installed version
+    :param source: where the files come from
+    """
+    record = {
+        'kind': kind,
+        'emu_type': emu_type,
+        'version': version,
+        'source': source,
+        'time': datetime.now().strftime('%Y-%m-%d %H:%M:%S'),
+        **kwargs,
+    }
+    logger.info(f'add install history: {record}')
+    config.install_history.append(record)
+    del config.install_history[:-max_install_history]
+    dump_config()
+    return record
+
+
+def get_install_history(kind=None, emu_type=None):
+    return [record for record in reversed(config.install_history)
+            if (not kind or record.get('kind') == kind) and (not emu_type or record.get('emu_type') == emu_type)]
+
+
+def clear_install_history(kind=None):
+    logger.info(f'clear install history, kind: {kind}')
+    config.install_history = [record for record in config.install_history if kind and record.get('kind') != kind]
+    dump_config()
+
+
+def record_keys_if_changed(emu_type: str):
+    from module.keys import get_keys_folder
+    prod_keys_path = get_keys_folder(emu_type).joinpath('prod.keys')
+    if not prod_keys_path.exists():
+        return
+    modified = datetime.fromtimestamp(prod_keys_path.stat().st_mtime).strftime('%Y-%m-%d %H:%M:%S')
+    last_records = get_install_history('keys', emu_type)
+    if last_records and last_records[0].get('modified') == modified:
+        return
+    add_install_history('keys', emu_type, source=str(prod_keys_path.absolute()), modified=modified)
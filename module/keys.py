@@ -0,0 +1,95 @@
+import logging
+import re
+from pathlib import Path
+from module.msg_notifier import send_notify_leveled
+
+logger = logging.getLogger(__name__)
+key_line_re = re.compile(r'^\s*([\w]+)\s*=\s*([\da-fA-F]+)\s*$')
+title_key_name_re = re.compile(r'^[\da-fA-F]{32}$')
+archive_magics = (b'PK\x03\x04', b'7z\xbc\xaf\x27\x1c', b'Rar!')
+keys_hint_file_name = '把prod.keys和title.keys放当前目录.txt'
+
+
+def get_keys_folder(emu_type: str):
+    if emu_type == 'yuzu':
+        from module.yuzu import get_yuzu_user_path
+        return get_yuzu_user_path().joinpath('keys')
+    else:
+        from module.ryujinx import get_ryujinx_user_folder
+        return get_ryujinx_user_folder().joinpath('system')
+
+
+def get_keys_status(emu_type: str):
+    keys_folder = get_keys_folder(emu_type)
+    return {
+        'keys_path': str(keys_folder.absolute()),
+        'prod_keys': keys_folder.joinpath('prod.keys').exists(),
+        'title_keys': keys_folder.joinpath('title.keys').exists(),
+    }
+
+
+def notify_missing_keys(emu_type: str):
+    status = get_keys_status(emu_type)
+    logger.info(f'{emu_type} keys status: {status}')
+    check_keys_folder(emu_type)
+    from module.install_history import record_keys_if_changed
+    record_keys_if_changed(emu_type)
+    if not status['prod_keys']:
+        send_notify_leveled('warning', f'未检测到 prod.keys, 请将 prod.keys 放至 {status["keys_path"]}')
+    if not status['title_keys']:
+        send_notify_leveled('warning', f'未检测到 title.keys, 部分加密的游戏可能需要 title.keys 才能运行, '
+                                       f'如有需要请将其放至 {status["keys_path"]}')
+    return status
+
+
+def classify_keys_file(path):
+    """
+    check whether the file is really a keys file
+    :param path: path of file
+    :return: one of prod_keys, title_keys, archive, empty, invalid
+    """
+    path = Path(path)
+    with open(path, 'rb') as f:
+        data = f.read(1024 * 1024)
+    if any(data.startswith(magic) for magic in archive_magics):
+        return 'archive'
+    text = data.decode('utf-8-sig', errors='ignore')
+    # blank lines and ; comments are allowed in keys files
+    lines = [line for line in text.splitlines() if line.strip() and not line.strip().startswith(';')]
+    if not lines:
+        return 'empty'
+    names = []
+    for line in lines:
+        m = key_line_re.match(line)
+        if not m:
+            return 'invalid'
+        names.append(m.group(1))
+    if all(title_key_name_re.match(name) for name in names):
+        return 'title_keys'
+    return 'prod_keys'
+
+
+def check_keys_folder(emu_type: str):
+    keys_folder = get_keys_folder(emu_type)
+    res = {}
+    if not keys_folder.exists():
+        return res
+    for file in keys_folder.iterdir():
+        if not file.is_file() or file.name == keys_hint_file_name:
+            continue
+        name = file.name.lower()
+        if not (name.endswith('.keys') or 'keys' in name or name.endswith(('.zip', '.7z', '.rar'))):
+            continue
+        kind = classify_keys_file(file)
+        res[file.name] = kind
+        if kind == 'archive':
+            send_notify_leveled('warning', f'{file.name} 是一个压缩包, 请先解压并将其中的 keys 文件放至 {keys_folder}')
+        elif kind in ('invalid', 'empty'):
+            send_notify_leveled('warning', f'{file.name} 不是有效的 keys 文件, 可能放错了文件')
+        elif not name.endswith('.keys'):
+            send_notify_leveled('warning', f'{file.name} 看起来是 keys 文件, 但文件名不正确, '
+                                           f'请重命名为 prod.keys 或 title.keys')
+        elif name == 'prod.keys' and kind == 'title_keys':
+            send_notify_leveled('warning', 'prod.keys 的内容看起来是 title.keys, 请检查是否放错了文件')
+    logger.info(f'{emu_type} keys folder check result: {res}')
+    return res
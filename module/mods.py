@@ -0,0 +1,69 @@
+import shutil
+from pathlib import Path
+import logging
+from module.msg_notifier import send_notify
+
+logger = logging.getLogger(__name__)
+# folders managed by the cheats feature rather than real mods
+reserved_mod_folder_names = {'cheats', 'cheats_chunk'}
+
+
+def get_disabled_mod_root_path(emu_type: str):
+    from module.cheats import get_mod_root_path
+    root = Path(get_mod_root_path(emu_type))
+    # emulators load every sub folder, so disabled mods are moved out of the mod root
+    return root.with_name(root.name + '_disabled')
+
+
+def _get_title_folders(title_id: str, emu_type: str):
+    from module.cheats import get_mod_root_path, game_id_re
+    if not game_id_re.match(title_id):
+        raise RuntimeError(f'无效的 title id: {title_id}')
+    return _find_title_folder(Path(get_mod_root_path(emu_type)), title_id), \
+        _find_title_folder(get_disabled_mod_root_path(emu_type), title_id)
+
+
+def _find_title_folder(root: Path, title_id: str):
+    if root.exists():
+        for folder in root.iterdir():
+            if folder.is_dir() and folder.name.lower() == title_id.lower():
+                return folder
+    return root.joinpath(title_id.upper())
+
+
+def _list_mod_folders(folder: Path):
+    if not folder.exists():
+        return []
+    return [p for p in folder.iterdir() if p.is_dir() and p.name.lower() not in reserved_mod_folder_names]
+
+
+def list_mods(title_id: str, emu_type: str = 'yuzu'):
+    enabled_folder, disabled_folder = _get_title_folders(title_id, emu_type)
+    res = []
+    for enabled, folder in ((True, enabled_folder), (False, disabled_folder)):
+        for mod_folder in _list_mod_folders(folder):
+            res.append({
+                'name': mod_folder.name,
+                'enabled': enabled,
+                'path': str(mod_folder.absolute()),
+            })
+    res.sort(key=lambda x: x['name'].lower())
+    return res
+
+
+def set_mod_enabled(title_id: str, mod_name: str, enabled: bool, emu_type: str = 'yuzu'):
+    enabled_folder, disabled_folder = _get_title_folders(title_id, emu_type)
+    if Path(mod_name).name != mod_name or mod_name.lower() in reserved_mod_folder_names:
+        raise RuntimeError(f'无效的 mod 名称: {mod_name}')
+    src, dst = (disabled_folder, enabled_folder) if enabled else (enabled_folder, disabled_folder)
+    src_mod, dst_mod = src.joinpath(mod_name), dst.joinpath(mod_name)
+    if dst_mod.exists():
+        logger.info(f'mod {dst_mod} already in target state.')
+        return list_mods(title_id, emu_type)
+    if not src_mod.exists():
+        raise RuntimeError(f'mod {mod_name} 不存在.')
+    dst.mkdir(parents=True, exist_ok=True)
+    logger.info(f'move mod {src_mod} to {dst_mod}')
+    shutil.move(str(src_mod), str(dst_mod))
+    send_notify(f'已{"启用" if enabled else "禁用"} mod: {mod_name}')
+    return list_mods(title_id, emu_type)
@@ -1,30 +1,101 @@
+import logging
+import time
+
+
+logger = logging.getLogger(__name__)
+notify_levels = ('info', 'warning', 'error')
+notify_dedup_interval = 1
+last_leveled_notify = (None, None, 0.)
+
 
 def dummy_notifier(msg):
     pass
 
 
+def dummy_leveled_notifier(level, msg):
+    pass
+
+
 def eel_notifier(msg):
     import eel
     eel.updateTopBarMsg(msg)
 
 
+def eel_leveled_notifier(level, msg):
+    import eel
+    eel.appendLeveledConsoleMessage(level, msg)
+
+
 def eel_console_notifier(msg):
     import eel
     eel.appendConsoleMessage(msg)
 
 
+def eel_console_leveled_notifier(level, msg):
+    import eel
+    eel.appendLeveledConsoleMessage(level, msg)
+
+
+def eel_download_event_forwarder(event):
+    import eel
+    eel.onDownloadEvent(event)
+
+
+def watch_config_changes(interval=2):
+    """
+    reload config when the file is changed outside and tell the frontend, runs in an eel greenlet
+    """
+    import eel
+    from config import reload_config_if_changed
+    while True:
+        eel.sleep(interval)
+        try:
+            if reload_config_if_changed():
+                eel.onConfigReloaded()
+        except Exception as e:
+            logger.warning(f'fail to reload config, msg: {str(e)}')
+
+
+def console_notifier(msg):
+    print(msg, flush=True)
+
+
+def console_leveled_notifier(level, msg):
+    print(f'[{level}] {msg}', flush=True)
+
+
 notifier = dummy_notifier
+leveled_notifier = dummy_leveled_notifier
 
 
 def update_notifier(mode):
     global notifier
+    global leveled_notifier
     if mode == 'eel':
         notifier = eel_notifier
+        leveled_notifier = eel_leveled_notifier
     elif mode == 'eel-console':
         notifier = eel_console_notifier
+        leveled_notifier = eel_console_leveled_notifier
+    elif mode == 'console':
+        notifier = console_notifier
+        leveled_notifier = console_leveled_notifier
     else:
         notifier = dummy_notifier
+        leveled_notifier = dummy_leveled_notifier
 
 
 def send_notify(msg):
     notifier(msg)
+
+
+def send_notify_leveled(level, msg):
+    global last_leveled_notify
+    if level not in notify_levels:
+        level = 'info'
+    now = time.time()
+    last_level, last_msg, last_time = last_leveled_notify
+    if level == last_level and msg == last_msg and now - last_time < notify_dedup_interval:
+        return
+    last_leveled_notify = (level, msg, now)
+    leveled_notifier(level, msg)
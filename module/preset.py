@@ -0,0 +1,72 @@
+import json
+import logging
+from config import config, dump_config
+from module.msg_notifier import send_notify
+
+logger = logging.getLogger(__name__)
+preset_format_version = 1
+preset_network_fields = ['githubApiMode', 'githubDownloadSource', 'mirrorPriority']
+
+
+def export_preset():
+    """
+    export current emulator versions and mirror preference as a sharable preset, local paths are excluded
+    """
+    preset = {'format_version': preset_format_version}
+    if config.yuzu.yuzu_version:
+        preset['yuzu'] = {
+            'branch': config.yuzu.branch,
+            'version': config.yuzu.yuzu_version,
+            'firmware': config.yuzu.yuzu_firmware,
+        }
+    if config.ryujinx.version:
+        preset['ryujinx'] = {
+            'branch': config.ryujinx.branch,
+            'version': config.ryujinx.version,
+            'firmware': config.ryujinx.firmware,
+        }
+    network_setting = config.setting.network
+    preset['network'] = {name: getattr(network_setting, name) for name in preset_network_fields}
+    # custom firmware sources are defined locally and can't be shared by name
+    if network_setting.firmwareSource in ('auto-detect', 'cdn', 'direct'):
+        preset['network']['firmwareSource'] = network_setting.firmwareSource
+    return json.dumps(preset, ensure_ascii=False, indent=2)
+
+
+def parse_preset(preset):
+    if isinstance(preset, str):
+        try:
+            preset = json.loads(preset)
+        except ValueError as e:
+            raise RuntimeError(f'无效的预设: {str(e)}')
+    if not isinstance(preset, dict) or preset.get('format_version') != preset_format_version:
+        raise RuntimeError('无效的预设或不支持的预设版本.')
+    for emu_type in ('yuzu', 'ryujinx'):
+        if emu_type in preset and not (preset[emu_type] or {}).get('version'):
+            raise RuntimeError(f'预设中缺少 {emu_type} 的版本信息.')
+    return preset
+
+
+def apply_preset(preset):
+    """
+    apply mirror preference in preset and install emulators and firmware in it
+    :return: setup result of each emulator
+    """
+    from module.common import setup_all
+    preset = parse_preset(preset)
+    network_preset = preset.get('network') or {}
+    for name in preset_network_fields + ['firmwareSource']:
+        if name in network_preset:
+            setattr(config.setting.network, name, network_preset[name])
+    dump_config()
+    res = {}
+    for emu_type in ('yuzu', 'ryujinx'):
+        if emu_type not in preset:
+            continue
+        emu_preset = preset[emu_type]
+        logger.info(f'apply {emu_type} preset: {emu_preset}')
+        send_notify(f'正在按预设配置 {emu_type}...')
+        res[emu_type] = setup_all(emu_type, emu_preset['version'], emu_preset.get('firmware'),
+                                  emu_preset.get('branch'))
+    send_notify('预设已应用')
+    return res
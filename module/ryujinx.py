@@ -3,10 +3,14 @@ import subprocess
 import time
 from pathlib import Path
 
-from module.downloader import download
+from module.downloader import download, get_cached_download_path
 from repository.ryujinx import get_ryujinx_release_info_by_version
 from utils.network import get_github_download_url
 from module.msg_notifier import send_notify
+from module.install_control import wait_if_installation_paused, timed_install, mark_install_step, \
+    build_install_result
+from utils.i18n import tr, get_emu_name
+from utils.common import open_folder, popen_hidden
 from config import config, dump_config
 import logging
 import os
@@ -15,85 +19,160 @@ import os
 logger = logging.getLogger(__name__)
 
 
-def get_ryujinx_download_url(target_version: str, branch: str):
+def get_ryujinx_download_asset(target_version: str, branch: str):
     release_info = get_ryujinx_release_info_by_version(target_version)
-    assets = release_info['assets']
+    assets = release_info.get('assets') or []
+    fallback_asset = None
     for asset in assets:
-        name: str = asset['name']
+        name: str = asset['name'].lower()
         if branch == 'mainline' and name.startswith('ryujinx-') and name.endswith('-win_x64.zip'):
-            return asset['browser_download_url']
+            return asset
         elif branch == 'ava' and name.startswith('test-ava-ryujinx-') and name.endswith('-win_x64.zip'):
-            return asset['browser_download_url']
+            return asset
+        elif not fallback_asset and name.startswith('ryujinx-') and name.endswith('-win_x64.zip'):
+            fallback_asset = asset
+    # forks like Ryubing only publish a single avalonia build without the test-ava prefix
+    if fallback_asset:
+        logger.info(f'no {branch} asset found in release {target_version}, use fallback: {fallback_asset["name"]}')
+    return fallback_asset
+
 
+def get_ryujinx_download_url(target_version: str, branch: str):
+    asset = get_ryujinx_download_asset(target_version, branch)
+    return asset['browser_download_url'] if asset else None
 
-def install_ryujinx_by_version(target_version: str, branch: str):
+
+def install_ryujinx_by_version(target_version: str, branch: str, dry_run=False, allow_conflict=False):
     if config.ryujinx.version == target_version and detect_current_branch() == branch:
         logger.info(f'Current ryujinx version is same as target version [{target_version}], skip install.')
-        return f'当前就是 {branch} [{target_version}] 版本的 ryujinx , 跳过安装.'
-    send_notify('正在获取 ryujinx 版本信息...')
-    download_url = get_ryujinx_download_url(target_version, branch)
-    if not download_url:
-        send_notify(f'获取 ryujinx 下载链接失败')
+        msg = tr('install.same_version', emu=get_emu_name('ryujinx'), branch=branch, version=target_version)
+        return build_install_result(Path(config.ryujinx.path).absolute(), 0, msg)
+    send_notify(tr('install.fetching_version', emu=get_emu_name('ryujinx')))
+    asset = get_ryujinx_download_asset(target_version, branch)
+    if not asset:
+        send_notify(tr('install.download_url_failed', emu=get_emu_name('ryujinx')))
         raise RuntimeError(f'No download url found with version: {target_version}')
-    download_url = get_github_download_url(download_url)
-    logger.info(f'download ryujinx from url: {download_url}')
-    send_notify(f'开始下载 ryujinx ...')
-    info = download(download_url)
-    file = info.files[0]
-    ryujinx_path = Path(config.ryujinx.path)
-    ryujinx_path.mkdir(parents=True, exist_ok=True)
-    clear_ryujinx_folder(ryujinx_path)
-    import zipfile
-    with zipfile.ZipFile(file.path, 'r') as zf:
+    download_url = get_github_download_url(asset['browser_download_url'])
+    if dry_run:
+        return get_ryujinx_install_plan(target_version, branch, download_url)
+    from module.common import check_emulator_conflicts
+    check_emulator_conflicts(Path(config.ryujinx.path), 'ryujinx', allow_conflict)
+    with timed_install('ryujinx', tr('step.download_emu', emu=get_emu_name('ryujinx'))) as timing:
+        package_path = get_cached_download_path(asset['name'], asset.get('size'))
+        if package_path:
+            logger.info(f'using cached ryujinx package: {package_path}')
+            send_notify(tr('install.using_cached_package', emu=get_emu_name('ryujinx'), path=package_path))
+        else:
+            logger.info(f'download ryujinx from url: {download_url}')
+            send_notify(tr('install.start_download', emu=get_emu_name('ryujinx')))
+            info = download(download_url)
+            package_path = info.files[0].path
+        package_size = os.path.getsize(package_path)
+        wait_if_installation_paused(tr('step.unzip_emu', emu=get_emu_name('ryujinx')))
+        ryujinx_path = Path(config.ryujinx.path)
+        ryujinx_path.mkdir(parents=True, exist_ok=True)
+        from module.emu_config import backup_emulator_config
+        backup_emulator_config('ryujinx')
         import tempfile
+        from utils.archive import uncompress
         tmp_dir = Path(tempfile.gettempdir()).joinpath('ryujinx-install')
-        logger.info(f'Unpacking ryujinx files to {tmp_dir}.')
-        send_notify('正在解压 ryujinx 文件...')
-        zf.extractall(str(tmp_dir.absolute()))
-        ryujinx_tmp_dir = tmp_dir.joinpath('publish')
-        logger.info(f'Copy back ryujinx files...')
-        send_notify('安装 ryujinx 文件至目录...')
-        kill_all_ryujinx_instance()
-        shutil.copytree(ryujinx_tmp_dir, ryujinx_path, dirs_exist_ok=True)
-        shutil.rmtree(tmp_dir)
+        shutil.rmtree(tmp_dir, ignore_errors=True)
+        try:
+            logger.info(f'Unpacking ryujinx files to {tmp_dir}.')
+            send_notify(tr('install.unzipping', emu=get_emu_name('ryujinx')))
+            uncompress(package_path, tmp_dir)
+            ryujinx_tmp_dir = find_ryujinx_release_dir(tmp_dir)
+            wait_if_installation_paused(tr('step.install_emu_files', emu=get_emu_name('ryujinx')))
+            from utils.common import check_target_path_length
+            check_target_path_length(ryujinx_tmp_dir, ryujinx_path)
+            kill_all_ryujinx_instance()
+            clear_ryujinx_folder(ryujinx_path)
+            logger.info(f'Copy back ryujinx files...')
+            send_notify(tr('install.copying_files', emu=get_emu_name('ryujinx')))
+            shutil.copytree(ryujinx_tmp_dir, ryujinx_path, dirs_exist_ok=True)
+        finally:
+            shutil.rmtree(tmp_dir, ignore_errors=True)
+        mark_install_step(tr('step.finish_install'))
         config.ryujinx.version = target_version
         config.ryujinx.branch = branch
         dump_config()
         logger.info(f'Ryujinx of [{target_version}] install successfully.')
-    if config.setting.download.autoDeleteAfterInstall:
-        os.remove(file.path)
-    from module.common import check_and_install_msvc
-    check_and_install_msvc()
-    return f'Ryujinx [{target_version}] 安装完成.'
+        if config.setting.download.autoDeleteAfterInstall:
+            os.remove(package_path)
+        from module.common import check_and_install_msvc
+        check_and_install_msvc()
+    msg = tr('install.success', emu=get_emu_name('ryujinx'), branch=branch, version=target_version,
+             path=ryujinx_path.absolute())
+    return build_install_result(ryujinx_path.absolute(), package_size, msg, timing.duration_ms)
+
+
+def get_ryujinx_install_plan(target_version: str, branch: str, download_url: str):
+    from module.emu_config import get_emulator_config_path
+    from module.common import detect_emulator_conflicts
+    ryujinx_path = Path(config.ryujinx.path)
+    plan = {
+        'version': target_version,
+        'branch': branch,
+        'download_url': download_url,
+        'install_path': str(ryujinx_path.absolute()),
+        'removed_files': [p.name for p in ryujinx_path.glob('*') if p.name != 'portable'],
+        'backup_config': get_emulator_config_path('ryujinx').exists(),
+        'conflicts': detect_emulator_conflicts(ryujinx_path, 'ryujinx'),
+    }
+    logger.info(f'ryujinx install plan: {plan}')
+    send_notify(f'[dry-run] 将从 {download_url} 下载 Ryujinx {branch} [{target_version}] 并安装至 '
+                f'{plan["install_path"]}, 将清除 {len(plan["removed_files"])} 个旧文件/目录')
+    return plan
+
+
+def find_ryujinx_release_dir(tmp_dir: Path):
+    if tmp_dir.joinpath('publish').is_dir():
+        return tmp_dir.joinpath('publish')
+    # some forks put the executable at the top level of the package or in other folder
+    for exe_name in ('Ryujinx.Ava.exe', 'Ryujinx.exe'):
+        for exe_path in sorted(tmp_dir.rglob(exe_name), key=lambda p: len(p.parts)):
+            logger.info(f'publish folder not found, using {exe_path.parent} as release folder.')
+            return exe_path.parent
+    raise RuntimeError(f'未能在压缩包中找到 Ryujinx 程序文件.')
 
 
 def install_firmware_to_ryujinx(firmware_version=None):
     if firmware_version == config.ryujinx.firmware:
         logger.info(f'Current firmware are same as target version [{firmware_version}], skip install.')
-        send_notify(f'当前的 固件 就是 [{firmware_version}], 跳过安装.')
-        return
+        send_notify(tr('firmware.same_version', version=firmware_version))
+        return firmware_version
     firmware_path = get_ryujinx_user_folder().joinpath(r'bis\system\Contents\registered')
     shutil.rmtree(firmware_path, ignore_errors=True)
     firmware_path.mkdir(parents=True, exist_ok=True)
     tmp_dir = firmware_path.joinpath('tmp/')
     from module.common import install_firmware
-    new_version = install_firmware(firmware_version, tmp_dir)
-    if new_version:
-        for path in tmp_dir.glob('*.nca'):
-            name = path.name[:-9] + '.nca' if path.name.endswith('.cnmt.nca') else path.name
-            nca_dir = firmware_path.joinpath(name)
-            nca_dir.mkdir()
-            path.rename(nca_dir.joinpath('00'))
+    try:
+        new_version = install_firmware(firmware_version, tmp_dir)
+        if new_version:
+            for path in tmp_dir.glob('*.nca'):
+                name = path.name[:-9] + '.nca' if path.name.endswith('.cnmt.nca') else path.name
+                nca_dir = firmware_path.joinpath(name)
+                nca_dir.mkdir()
+                path.rename(nca_dir.joinpath('00'))
+    finally:
         shutil.rmtree(tmp_dir, ignore_errors=True)
+    if new_version:
+        from module.firmware import verify_installed_firmware_version
+        new_version = verify_installed_firmware_version('ryujinx', new_version)
         config.ryujinx.firmware = new_version
         dump_config()
-        send_notify(f'固件 [{firmware_version}] 安装成功，请安装相应的 key 至 Ryujinx.')
+        from module.install_history import add_install_history
+        add_install_history('firmware', 'ryujinx', new_version, config.setting.network.firmwareSource)
+        send_notify(tr('firmware.success', version=firmware_version, emu=get_emu_name('ryujinx')))
+        from module.keys import notify_missing_keys
+        notify_missing_keys('ryujinx')
+    return new_version
 
 
-def clear_ryujinx_folder(ryujinx_path: Path):
+def clear_ryujinx_folder(ryujinx_path: Path, keep_user_data=True):
     send_notify('清除旧版 ryujinx 文件...')
     for path in ryujinx_path.glob('*'):
-        if path.name == 'portable':
+        if keep_user_data and path.name == 'portable':
             continue
         logger.debug(f'removing path: {path}')
         if path.is_dir():
@@ -102,6 +181,36 @@ def clear_ryujinx_folder(ryujinx_path: Path):
             os.remove(path)
 
 
+def uninstall_ryujinx(keep_user_data: bool = True):
+    ryujinx_path = Path(config.ryujinx.path)
+    if not ryujinx_path.exists():
+        raise RuntimeError(f'Ryujinx 目录 [{ryujinx_path}] 不存在.')
+    from module.common import detect_emulator_conflicts
+    conflicts = detect_emulator_conflicts(ryujinx_path, 'ryujinx')
+    if conflicts:
+        files = ', '.join(item['file'] for item in conflicts)
+        raise RuntimeError(f'Ryujinx 目录中存在其它模拟器的文件 ({files}), 为避免误删, 请手动卸载.')
+    kill_all_ryujinx_instance()
+    logger.info(f'uninstall Ryujinx in {ryujinx_path}, keep_user_data: {keep_user_data}')
+    clear_ryujinx_folder(ryujinx_path, keep_user_data)
+    config.ryujinx.version = None
+    if not keep_user_data:
+        config.ryujinx.firmware = None
+    dump_config()
+    send_notify(f'Ryujinx 已卸载{", 用户数据已保留" if keep_user_data else ""}')
+
+
+def is_ryujinx_running():
+    import psutil
+    for p in psutil.process_iter():
+        try:
+            if p.name().startswith('Ryujinx.'):
+                return True
+        except psutil.Error:
+            continue
+    return False
+
+
 def kill_all_ryujinx_instance():
     import psutil
     kill_flag = False
@@ -115,15 +224,43 @@ def kill_all_ryujinx_instance():
         time.sleep(1)
 
 
-def get_ryujinx_user_folder():
-    ryujinx_path = Path(config.ryujinx.path)
+def get_ryujinx_appdata_folder():
+    return Path(os.environ['appdata']).joinpath('Ryujinx/')
+
+
+def set_ryujinx_portable(enabled: bool, migrate: bool = True):
+    from module.common import migrate_user_folder, rename_to_backup_folder
+    portable_folder = Path(config.ryujinx.path).joinpath('portable/')
+    appdata_folder = get_ryujinx_appdata_folder()
+    kill_all_ryujinx_instance()
+    if enabled:
+        if migrate and not portable_folder.exists():
+            migrate_user_folder(appdata_folder, portable_folder)
+        portable_folder.mkdir(parents=True, exist_ok=True)
+    elif portable_folder.exists():
+        if migrate:
+            migrate_user_folder(portable_folder, appdata_folder)
+        else:
+            rename_to_backup_folder(portable_folder)
+    user_folder = get_ryujinx_user_folder()
+    logger.info(f'ryujinx portable: {enabled}, current user folder: {user_folder}')
+    send_notify(f'当前 Ryujinx 用户目录: {user_folder}')
+    return str(user_folder.absolute())
+
+
+def get_ryujinx_user_folder(ryujinx_path=None):
+    ryujinx_path = Path(ryujinx_path or config.ryujinx.path)
     if ryujinx_path.joinpath('portable/').exists():
         return ryujinx_path.joinpath('portable/')
-    elif Path(os.environ['appdata']).joinpath('Ryujinx/').exists():
-        return Path(os.environ['appdata']).joinpath('Ryujinx/')
+    elif get_ryujinx_appdata_folder().exists():
+        return get_ryujinx_appdata_folder()
     return ryujinx_path.joinpath('portable/')
 
 
+def get_ryujinx_mods_path():
+    return get_ryujinx_user_folder().joinpath('mods/contents')
+
+
 def get_ryujinx_exe_path():
     ryujinx_path = Path(config.ryujinx.path)
     if ryujinx_path.joinpath('Ryujinx.Ava.exe').exists():
@@ -137,7 +274,7 @@ def open_ryujinx_keys_folder():
     keys_path.mkdir(parents=True, exist_ok=True)
     keys_path.joinpath('把prod.keys放当前目录.txt').touch(exist_ok=True)
     logger.info(f'open explorer on path {keys_path}')
-    subprocess.Popen(f'explorer "{str(keys_path.absolute())}"')
+    open_folder(keys_path)
 
 
 def start_ryujinx():
@@ -166,13 +303,14 @@ def detect_ryujinx_version():
     if not rj_path:
         send_notify('未能找到 Ryujinx 程序')
         return None
+    if is_ryujinx_running():
+        send_notify('Ryujinx 正在运行, 请先关闭 Ryujinx 后再检测版本')
+        raise RuntimeError('Ryujinx is running, please close it before detecting version.')
     config.ryujinx.branch = detect_current_branch()
-    st_inf = subprocess.STARTUPINFO()
-    st_inf.dwFlags = st_inf.dwFlags | subprocess.STARTF_USESHOWWINDOW
-    subprocess.Popen([rj_path], startupinfo=st_inf, shell=True, stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL)
-    time.sleep(3)
     version = None
     try:
+        popen_hidden([rj_path], shell=True, stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL)
+        time.sleep(3)
         from utils.common import get_all_window_name
         for window_name in get_all_window_name():
             if window_name.startswith('Ryujinx '):
@@ -180,9 +318,10 @@ def detect_ryujinx_version():
                 send_notify(f'当前 Ryujinx 版本 [{version}]')
                 logger.info(f'Current Ryujinx version: {version}')
                 break
-    except:
-        logger.exception('error occur in get_all_window_name')
-    kill_all_ryujinx_instance()
+    except Exception:
+        logger.exception('error occur in detecting Ryujinx version')
+    finally:
+        kill_all_ryujinx_instance()
     if version:
         config.ryujinx.version = version
         dump_config()
@@ -29,9 +29,11 @@ DEL "%~f0"
 """
 
 
-def check_update(prerelease=False):
+def check_update(prerelease=None):
     from repository.my_info import get_all_release
-    from config import current_version
+    from config import current_version, config
+    if prerelease is None:
+        prerelease = config.setting.update.channel == 'beta'
     release_infos = get_all_release()
     latest_tag_name = None
     if prerelease:
@@ -43,6 +45,9 @@ def check_update(prerelease=False):
                 break
     if not latest_tag_name:
         latest_tag_name = release_infos[0]['tag_name']
+    if latest_tag_name == config.setting.update.skippedVersion:
+        logger.info(f'version [{latest_tag_name}] has been skipped.')
+        return False, latest_tag_name
     return current_version != latest_tag_name, latest_tag_name
 
 
@@ -6,11 +6,13 @@ import time
 from pathlib import Path
 import logging
 
-import py7zr
-
 from config import config, dump_config
-from module.downloader import download
+from module.downloader import download, get_cached_download_path
 from module.msg_notifier import send_notify
+from module.install_control import wait_if_installation_paused, timed_install, mark_install_step, \
+    build_install_result
+from utils.i18n import tr, get_emu_name
+from utils.common import open_folder, popen_hidden
 from repository.yuzu import get_yuzu_release_info_by_version
 from utils.network import get_github_download_url
 
@@ -18,128 +20,210 @@ from utils.network import get_github_download_url
 logger = logging.getLogger(__name__)
 
 
-def download_yuzu(target_version, branch):
-    send_notify('正在获取 yuzu 版本信息...')
+def get_yuzu_download_asset(target_version, branch):
+    send_notify(tr('install.fetching_version', emu=get_emu_name('yuzu')))
     release_info = get_yuzu_release_info_by_version(target_version, branch)
     if not release_info.get('tag_name'):
         logger.error(f'fail to get release info of version {target_version} on branch {branch}')
-        send_notify(f'无法获取 {branch} 分支的 [{target_version}] 版本信息')
+        send_notify(tr('install.release_not_found', branch=branch, version=target_version))
         raise RuntimeError(f'fail to get release info of version {target_version} on branch {branch}')
     logger.info(f'target yuzu version: {target_version}')
     yuzu_path = Path(config.yuzu.yuzu_path)
     logger.info(f'target yuzu path: {yuzu_path}')
-    send_notify('开始下载 yuzu...')
-    assets = release_info['assets']
-    url = None
-    for asset in assets:
+    asset = _select_yuzu_asset(release_info)
+    if asset:
+        return asset
+    raise RuntimeError('Fail to fetch yuzu download url.')
+
+
+def _select_yuzu_asset(release_info):
+    for asset in release_info.get('assets') or []:
         if asset['content_type'] == 'application/x-7z-compressed':
-            url = get_github_download_url(asset['browser_download_url'])
-            break
+            return asset
         elif asset['name'].startswith('Windows-Yuzu-EA-') and asset['name'].endswith('.zip'):
-            url = get_github_download_url(asset['browser_download_url'])
-            break
-    if not url:
-        raise RuntimeError('Fail to fetch yuzu download url.')
+            return asset
+    return None
+
+
+def find_yuzu_download_asset(target_version, branch):
+    """
+    same as get_yuzu_download_asset but returns None silently if the release or asset does not exist
+    """
+    release_info = get_yuzu_release_info_by_version(target_version, branch)
+    if not release_info.get('tag_name'):
+        return None
+    return _select_yuzu_asset(release_info)
+
+
+def get_yuzu_download_url(target_version, branch):
+    return get_github_download_url(get_yuzu_download_asset(target_version, branch)['browser_download_url'])
+
+
+def download_yuzu(target_version, branch):
+    asset = get_yuzu_download_asset(target_version, branch)
+    cached_path = get_cached_download_path(asset['name'], asset.get('size'))
+    if cached_path:
+        logger.info(f'using cached yuzu package: {cached_path}')
+        send_notify(tr('install.using_cached_package', emu=get_emu_name('yuzu'), path=cached_path))
+        return cached_path
+    url = get_github_download_url(asset['browser_download_url'])
+    send_notify(tr('install.start_download', emu=get_emu_name('yuzu')))
     logger.info(f"downloading yuzu from {url}")
     info = download(url)
     file = info.files[0]
     return file.path
 
 
-def unzip_yuzu(package_path: Path):
-    logger.info(f'Unpacking yuzu files...')
-    send_notify('正在解压 yuzu 文件...')
-    if package_path.name.endswith('.zip'):
-        import zipfile
-        with zipfile.ZipFile(package_path, 'r') as zf:
-            zf.extractall(tempfile.gettempdir())
-            return tempfile.gettempdir()
-    elif package_path.name.endswith('.7z'):
-        with py7zr.SevenZipFile(package_path) as zf:
-            zf.extractall(tempfile.gettempdir())
-            return tempfile.gettempdir()
-    logger.info(f'Unknown file format: {package_path}')
-    send_notify('不支持的文件格式, 解压失败.')
+def unzip_yuzu(package_path: Path, target_dir: Path):
+    wait_if_installation_paused(tr('step.unzip_emu', emu=get_emu_name('yuzu')))
+    logger.info(f'Unpacking yuzu files to {target_dir}...')
+    send_notify(tr('install.unzipping', emu=get_emu_name('yuzu')))
+    from utils.archive import uncompress
+    uncompress(package_path, target_dir)
+    return target_dir
 
 
-def install_ea_yuzu(target_version):
+def find_yuzu_release_dir(tmp_dir: Path):
+    if tmp_dir.joinpath('yuzu.exe').exists():
+        return tmp_dir
+    sub_dirs = [p for p in tmp_dir.iterdir() if p.is_dir()]
+    for sub_dir in sub_dirs:
+        if sub_dir.joinpath('yuzu.exe').exists():
+            return sub_dir
+    if len(sub_dirs) == 1:
+        return sub_dirs[0]
+    logger.info(f'unable to find yuzu release folder in {tmp_dir}, use it directly.')
+    return tmp_dir
+
+
+def install_yuzu_package(yuzu_package_path: Path):
     yuzu_path = Path(config.yuzu.yuzu_path)
+    tmp_dir = Path(tempfile.gettempdir()).joinpath('yuzu-install')
+    shutil.rmtree(tmp_dir, ignore_errors=True)
+    try:
+        unzip_yuzu(yuzu_package_path, tmp_dir)
+        copy_back_yuzu_files(find_yuzu_release_dir(tmp_dir), yuzu_path)
+    finally:
+        shutil.rmtree(tmp_dir, ignore_errors=True)
+
+
+def install_ea_yuzu(target_version):
     yuzu_package_path = download_yuzu(target_version, 'ea')
-    unzip_yuzu(yuzu_package_path)
-    tmp_dir = Path(tempfile.gettempdir()).joinpath('yuzu-windows-msvc-early-access')
-    copy_back_yuzu_files(tmp_dir, yuzu_path)
+    package_size = os.path.getsize(yuzu_package_path)
+    install_yuzu_package(yuzu_package_path)
     logger.info(f'Yuzu EA of [{target_version}] install successfully.')
     if config.setting.download.autoDeleteAfterInstall:
         os.remove(yuzu_package_path)
+    return package_size
 
 
 def install_mainline_yuzu(target_version):
-    yuzu_path = Path(config.yuzu.yuzu_path)
     yuzu_package_path = download_yuzu(target_version, 'mainline')
-    unzip_yuzu(yuzu_package_path)
-    tmp_dir = Path(tempfile.gettempdir()).joinpath('yuzu-windows-msvc')
-    copy_back_yuzu_files(tmp_dir, yuzu_path)
+    package_size = os.path.getsize(yuzu_package_path)
+    install_yuzu_package(yuzu_package_path)
     logger.info(f'Yuzu mainline of [{target_version}] install successfully.')
     if config.setting.download.autoDeleteAfterInstall:
         os.remove(yuzu_package_path)
+    return package_size
 
 
 def copy_back_yuzu_files(tmp_dir: Path, yuzu_path: Path, ):
+    wait_if_installation_paused(tr('step.install_emu_files', emu=get_emu_name('yuzu')))
     for useless_file in tmp_dir.glob('yuzu-windows-msvc-source-*.tar.xz'):
         os.remove(useless_file)
+    from utils.common import check_target_path_length
+    check_target_path_length(tmp_dir, yuzu_path)
     logger.info(f'Copy back yuzu files...')
-    send_notify('安装 yuzu 文件至目录...')
+    send_notify(tr('install.copying_files', emu=get_emu_name('yuzu')))
     kill_all_yuzu_instance()
     shutil.copytree(tmp_dir, yuzu_path, dirs_exist_ok=True)
-    shutil.rmtree(tmp_dir)
 
 
-def install_yuzu(target_version, branch='ea'):
+def get_yuzu_install_plan(target_version, branch='ea'):
+    from module.emu_config import get_emulator_config_path
+    from module.common import detect_emulator_conflicts
+    yuzu_path = Path(config.yuzu.yuzu_path)
+    plan = {
+        'version': target_version,
+        'branch': branch,
+        'download_url': get_yuzu_download_url(target_version, branch),
+        'install_path': str(yuzu_path.absolute()),
+        'overwrite_existing': get_yuzu_exe_path().exists(),
+        'backup_config': get_emulator_config_path('yuzu').exists(),
+        'conflicts': detect_emulator_conflicts(yuzu_path, 'yuzu'),
+    }
+    logger.info(f'yuzu install plan: {plan}')
+    send_notify(f'[dry-run] 将从 {plan["download_url"]} 下载 yuzu {branch} [{target_version}] '
+                f'并安装至 {plan["install_path"]}{", 覆盖现有文件" if plan["overwrite_existing"] else ""}')
+    return plan
+
+
+def install_yuzu(target_version, branch='ea', dry_run=False, allow_conflict=False):
     if target_version == config.yuzu.yuzu_version:
         logger.info(f'Current yuzu version is same as target version [{target_version}], skip install.')
-        send_notify(f'当前就是 [{target_version}] 版本的 yuzu , 跳过安装.')
-        return
-    if branch == 'ea':
-        install_ea_yuzu(target_version)
-    else:
-        install_mainline_yuzu(target_version)
-    config.yuzu.yuzu_version = target_version
-    config.yuzu.branch = branch
-    dump_config()
-    from module.common import check_and_install_msvc
-    check_and_install_msvc()
-    send_notify(f'yuzu {branch} [{target_version}] 安装成功.')
+        msg = tr('install.same_version', emu=get_emu_name('yuzu'), branch=branch, version=target_version)
+        send_notify(msg)
+        return build_install_result(Path(config.yuzu.yuzu_path).absolute(), 0, msg)
+    if dry_run:
+        return get_yuzu_install_plan(target_version, branch)
+    from module.common import check_emulator_conflicts
+    check_emulator_conflicts(Path(config.yuzu.yuzu_path), 'yuzu', allow_conflict)
+    from module.emu_config import backup_emulator_config
+    backup_emulator_config('yuzu')
+    with timed_install('yuzu', tr('step.download_emu', emu=get_emu_name('yuzu'))) as timing:
+        if branch == 'ea':
+            package_size = install_ea_yuzu(target_version)
+        else:
+            package_size = install_mainline_yuzu(target_version)
+        mark_install_step(tr('step.finish_install'))
+        config.yuzu.yuzu_version = target_version
+        config.yuzu.branch = branch
+        dump_config()
+        from module.common import check_and_install_msvc
+        check_and_install_msvc()
+    msg = tr('install.success', emu=get_emu_name('yuzu'), branch=branch, version=target_version,
+             path=Path(config.yuzu.yuzu_path).absolute())
+    send_notify(msg)
+    return build_install_result(Path(config.yuzu.yuzu_path).absolute(), package_size, msg, timing.duration_ms)
 
 
 def install_firmware_to_yuzu(firmware_version=None):
     if firmware_version == config.yuzu.yuzu_firmware:
         logger.info(f'Current firmware are same as target version [{firmware_version}], skip install.')
-        send_notify(f'当前的 固件 就是 [{firmware_version}], 跳过安装.')
-        return
+        send_notify(tr('firmware.same_version', version=firmware_version))
+        return firmware_version
     from module.common import install_firmware
     new_version = install_firmware(firmware_version, get_yuzu_nand_path().joinpath(r'system\Contents\registered'))
     if new_version:
+        from module.firmware import verify_installed_firmware_version
+        new_version = verify_installed_firmware_version('yuzu', new_version)
         config.yuzu.yuzu_firmware = new_version
         dump_config()
-        send_notify(f'固件 [{firmware_version}] 安装成功，请安装相应的 key 至 yuzu.')
+        from module.install_history import add_install_history
+        add_install_history('firmware', 'yuzu', new_version, config.setting.network.firmwareSource)
+        send_notify(tr('firmware.success', version=firmware_version, emu=get_emu_name('yuzu')))
+        from module.keys import notify_missing_keys
+        notify_missing_keys('yuzu')
+    return new_version
 
 
 def detect_yuzu_version():
     send_notify('正在检测 yuzu 版本...')
-    yz_path = Path(config.yuzu.yuzu_path).joinpath('yuzu.exe')
+    yz_path = get_yuzu_exe_path()
     if not yz_path.exists():
         send_notify('未能找到 yuzu 程序')
         return None
-    kill_all_yuzu_instance()
-    st_inf = subprocess.STARTUPINFO()
-    st_inf.dwFlags = st_inf.dwFlags | subprocess.STARTF_USESHOWWINDOW
-    send_notify(f'正在启动 yuzu ...')
-    subprocess.Popen(['powershell', 'Start-Process', f'"{str(yz_path.absolute())}"', '-WindowStyle', 'Hidden'],
-                     startupinfo=st_inf)
-    time.sleep(3)
+    if is_yuzu_running():
+        send_notify('yuzu 正在运行, 请先关闭 yuzu 后再检测版本')
+        raise RuntimeError('yuzu is running, please close it before detecting version.')
     version = None
     branch = None
     try:
+        send_notify(f'正在启动 yuzu ...')
+        # use single quoted string so that powershell won't expand special chars like $ or ` in path
+        ps_yz_path = str(yz_path.absolute()).replace("'", "''")
+        popen_hidden(['powershell', 'Start-Process', '-FilePath', f"'{ps_yz_path}'", '-WindowStyle', 'Hidden'])
+        time.sleep(3)
         from utils.common import get_all_window_name
         for window_name in get_all_window_name():
             if window_name.startswith('yuzu '):
@@ -153,9 +237,10 @@ def detect_yuzu_version():
                 send_notify(f'当前 yuzu 版本 [{version}]')
                 logger.info(f'current yuzu version: {version}, branch: {branch}')
                 break
-    except:
-        logger.exception('error occur in get_all_window_name')
-    kill_all_yuzu_instance()
+    except Exception:
+        logger.exception('error occur in detecting yuzu version')
+    finally:
+        kill_all_yuzu_instance()
     if version:
         config.yuzu.yuzu_version = version
         config.yuzu.branch = branch
@@ -163,6 +248,75 @@ def detect_yuzu_version():
         return version
 
 
+yuzu_program_file_patterns = ('yuzu*.exe', '*.dll', '*.pdb', 'qt.conf', 'license*', 'readme*')
+yuzu_program_folders = {'imageformats', 'platforms', 'styles', 'iconengines', 'mediaservice', 'tls', 'translations',
+                        'multimedia', 'generic', 'networkinformation', 'position', 'bearer', 'sqldrivers'}
+
+
+def is_yuzu_program_file(path: Path):
+    from fnmatch import fnmatch
+    name = path.name.lower()
+    if path.is_dir():
+        return name in yuzu_program_folders
+    return any(fnmatch(name, pattern) for pattern in yuzu_program_file_patterns)
+
+
+def check_yuzu_folder_removable(yuzu_path: Path):
+    from module.common import detect_emulator_conflicts
+    conflicts = detect_emulator_conflicts(yuzu_path, 'yuzu')
+    if conflicts:
+        files = ', '.join(item['file'] for item in conflicts)
+        raise RuntimeError(f'yuzu 目录中存在其它模拟器的文件 ({files}), 为避免误删, 请手动卸载.')
+    unknown_files = [p.name for p in yuzu_path.iterdir() if p.name != 'user' and not is_yuzu_program_file(p)]
+    if unknown_files:
+        raise RuntimeError(f'yuzu 目录中存在非 yuzu 的文件 ({", ".join(unknown_files[:5])}), 为避免误删, 请手动卸载.')
+
+
+def remove_yuzu_program_files(yuzu_path: Path, keep_user_data=True):
+    send_notify('清除 yuzu 程序文件...')
+    for path in yuzu_path.glob('*'):
+        if path.name == 'user':
+            if keep_user_data:
+                continue
+        elif not is_yuzu_program_file(path):
+            logger.info(f'skip non-yuzu path: {path}')
+            continue
+        logger.debug(f'removing path: {path}')
+        if path.is_dir():
+            shutil.rmtree(path)
+        else:
+            os.remove(path)
+
+
+def uninstall_yuzu(keep_user_data: bool = True):
+    yuzu_path = Path(config.yuzu.yuzu_path)
+    if not yuzu_path.exists():
+        raise RuntimeError(f'yuzu 目录 [{yuzu_path}] 不存在.')
+    check_yuzu_folder_removable(yuzu_path)
+    from module.firmware import get_firmware_path
+    firmware_path = get_firmware_path('yuzu')
+    kill_all_yuzu_instance()
+    logger.info(f'uninstall yuzu in {yuzu_path}, keep_user_data: {keep_user_data}')
+    remove_yuzu_program_files(yuzu_path, keep_user_data)
+    config.yuzu.yuzu_version = None
+    # firmware may be kept in user folder or in custom nand folder
+    if not firmware_path.exists():
+        config.yuzu.yuzu_firmware = None
+    dump_config()
+    send_notify(f'yuzu 已卸载{", 用户数据已保留" if keep_user_data else ""}')
+
+
+def is_yuzu_running():
+    import psutil
+    for p in psutil.process_iter():
+        try:
+            if p.name() == 'yuzu.exe':
+                return True
+        except psutil.Error:
+            continue
+    return False
+
+
 def kill_all_yuzu_instance():
     import psutil
     kill_flag = False
@@ -177,7 +331,7 @@ def kill_all_yuzu_instance():
 
 
 def start_yuzu():
-    yz_path = Path(config.yuzu.yuzu_path).joinpath('yuzu.exe')
+    yz_path = get_yuzu_exe_path()
     if yz_path.exists():
         logger.info(f'starting yuzu from: {yz_path}')
         subprocess.Popen([yz_path])
@@ -186,21 +340,51 @@ def start_yuzu():
         raise RuntimeError(f'yuzu not exist in [{yz_path}]')
 
 
-def get_yuzu_user_path():
-    yuzu_path = Path(config.yuzu.yuzu_path)
+def get_yuzu_exe_path():
+    return Path(config.yuzu.yuzu_path).joinpath('yuzu.exe')
+
+
+def get_yuzu_appdata_path():
+    return Path(os.environ['appdata']).joinpath('yuzu/')
+
+
+def get_yuzu_user_path(yuzu_path=None):
+    yuzu_path = Path(yuzu_path or config.yuzu.yuzu_path)
     if yuzu_path.joinpath('user/').exists():
         return yuzu_path.joinpath('user/')
-    elif Path(os.environ['appdata']).joinpath('yuzu/').exists():
-        return Path(os.environ['appdata']).joinpath('yuzu/')
+    elif get_yuzu_appdata_path().exists():
+        return get_yuzu_appdata_path()
     return yuzu_path.joinpath('user/')
 
 
+def set_yuzu_portable(enabled: bool, migrate: bool = True):
+    from module.common import migrate_user_folder, rename_to_backup_folder
+    portable_path = Path(config.yuzu.yuzu_path).joinpath('user/')
+    appdata_path = get_yuzu_appdata_path()
+    kill_all_yuzu_instance()
+    if enabled:
+        if migrate and not portable_path.exists():
+            migrate_user_folder(appdata_path, portable_path)
+        portable_path.mkdir(parents=True, exist_ok=True)
+    elif portable_path.exists():
+        if migrate:
+            migrate_user_folder(portable_path, appdata_path)
+        else:
+            rename_to_backup_folder(portable_path)
+    user_path = get_yuzu_user_path()
+    logger.info(f'yuzu portable: {enabled}, current user path: {user_path}')
+    send_notify(f'当前 yuzu 用户目录: {user_path}')
+    return str(user_path.absolute())
+
+
 def open_yuzu_keys_folder():
     keys_path = get_yuzu_user_path().joinpath('keys')
     keys_path.mkdir(parents=True, exist_ok=True)
-    keys_path.joinpath('把prod.keys和title.keys放当前目录.txt').touch(exist_ok=True)
+    from module.keys import keys_hint_file_name, check_keys_folder
+    keys_path.joinpath(keys_hint_file_name).touch(exist_ok=True)
+    check_keys_folder('yuzu')
     logger.info(f'open explorer on path {keys_path}')
-    subprocess.Popen(f'explorer "{str(keys_path.absolute())}"')
+    open_folder(keys_path)
 
 
 def _get_yuzu_data_storage_config(user_path: Path):
@@ -216,33 +400,30 @@ def _get_yuzu_data_storage_config(user_path: Path):
         return data_storage
 
 
-def get_yuzu_nand_path():
-    user_path = get_yuzu_user_path()
-    nand_path = user_path.joinpath('nand')
+def _get_yuzu_data_storage_path(user_path: Path, config_key: str, default_path: Path):
     try:
         data_storage = _get_yuzu_data_storage_config(user_path)
-        if data_storage:
-            path_str = data_storage.get('nand_directory')
+        path_str = data_storage.get(config_key) if data_storage else None
+        if path_str:
             from utils.common import escape_yuzu_path
-            nand_path = Path(escape_yuzu_path(path_str))
-            logger.info(f'use nand path from yuzu config: {nand_path}')
+            path = Path(escape_yuzu_path(path_str))
+            if path.exists():
+                logger.info(f'use {config_key} from yuzu config: {path}')
+                return path
+            logger.warning(f'{config_key} [{path}] in yuzu config does not exist, fallback to {default_path}')
     except Exception as e:
         logger.warning(f'fail in parse yuzu qt-config, error msg: {str(e)}')
-    return nand_path
+    return default_path
 
 
-def get_yuzu_load_path():
-    user_path = get_yuzu_user_path()
-    load_path = user_path.joinpath('load')
-    try:
-        data_storage = _get_yuzu_data_storage_config(user_path)
-        if data_storage:
-            path_str = data_storage.get('load_directory')
-            load_path = Path(escape_yuzu_path(path_str))
-            logger.info(f'use load path from yuzu config: {load_path}')
-    except Exception as e:
-        logger.warning(f'fail in parse yuzu qt-config, error msg: {str(e)}')
-    return load_path
+def get_yuzu_nand_path(user_path=None):
+    user_path = user_path or get_yuzu_user_path()
+    return _get_yuzu_data_storage_path(user_path, 'nand_directory', user_path.joinpath('nand'))
+
+
+def get_yuzu_load_path(user_path=None):
+    user_path = user_path or get_yuzu_user_path()
+    return _get_yuzu_data_storage_path(user_path, 'load_directory', user_path.joinpath('load'))
 
 
 if __name__ == '__main__':
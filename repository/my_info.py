@@ -17,6 +17,7 @@ def get_release_info_by_tag(tag: str):
     return request_github_api(f'https://api.github.com/repos/triwinds/ns-emu-tools/releases/tags/{tag}')
 
 
-def load_change_log():
+def load_change_log(max_versions=None):
+    from utils.common import normalize_change_log
     resp = session.get(get_finial_url('https://raw.githubusercontent.com/triwinds/ns-emu-tools/main/changelog.md'))
-    return resp.text
+    return normalize_change_log(resp.text, max_versions)
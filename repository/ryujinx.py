@@ -1,8 +1,20 @@
-from utils.network import request_github_api, session, get_finial_url
+from utils.network import request_github_api
+from config import config
 
 
-def get_all_ryujinx_release_infos():
-    return request_github_api('https://api.github.com/repos/Ryujinx/release-channel-master/releases')
+default_ryujinx_repo = 'Ryubing/Ryujinx'
+
+
+def get_ryujinx_repo():
+    return config.setting.network.ryujinxRepo or default_ryujinx_repo
+
+
+def get_all_ryujinx_release_infos(limit=None, stable_only=False, since=None, until=None):
+    data = request_github_api(f'https://api.github.com/repos/{get_ryujinx_repo()}/releases')
+    if not any([limit, stable_only, since, until]):
+        return data
+    from utils.common import filter_release_infos
+    return filter_release_infos(data, limit, stable_only, since, until)
 
 
 def get_latest_ryujinx_release_info():
@@ -10,9 +22,12 @@ def get_latest_ryujinx_release_info():
 
 
 def get_ryujinx_release_info_by_version(version):
-    return request_github_api(f'https://api.github.com/repos/Ryujinx/release-channel-master/releases/tags/{version}')
+    return request_github_api(f'https://api.github.com/repos/{get_ryujinx_repo()}/releases/tags/{version}')
 
 
-def load_ryujinx_change_log():
-    resp = session.get(get_finial_url('https://raw.githubusercontent.com/wiki/Ryujinx/Ryujinx/Changelog.md'))
-    return resp.text
+def load_ryujinx_change_log(max_versions=None):
+    from utils.common import normalize_change_log
+    # build the change log from release notes, the configured repo may be a fork without the upstream wiki
+    release_infos = get_all_ryujinx_release_infos(limit=max_versions)
+    change_log = '\n\n'.join(f'## {info["tag_name"]}\n\n{info.get("body") or ""}' for info in release_infos)
+    return normalize_change_log(change_log, max_versions)
@@ -1,23 +1,57 @@
+import logging
 from utils.network import request_github_api
+from config import config
+
+logger = logging.getLogger(__name__)
+default_yuzu_repos = {
+    'ea': ['pineappleEA/pineapple-src'],
+    'mainline': ['yuzu-emu/yuzu-mainline'],
+}
+
+
+def get_yuzu_repo_candidates(branch: str):
+    branch = 'mainline' if branch.lower() == 'mainline' else 'ea'
+    overrides = config.setting.network.yuzuRepoCandidates or {}
+    return overrides.get(branch) or default_yuzu_repos[branch]
+
+
+def _request_release_list(branch: str):
+    for repo in get_yuzu_repo_candidates(branch):
+        try:
+            data = request_github_api(f'https://api.github.com/repos/{repo}/releases')
+            if isinstance(data, list):
+                return repo, data
+            logger.warning(f'fail to get releases from repo [{repo}], response: {data}')
+        except Exception as e:
+            logger.warning(f'fail to get releases from repo [{repo}], msg: {str(e)}')
+    raise RuntimeError(f'fail to get yuzu releases of branch {branch}')
+
+
+def _is_ea_release(repo: str, item):
+    if repo.split('/')[0] != 'pineappleEA':
+        return True
+    return item['author']['login'] == 'pineappleEA'
 
 
 def get_all_yuzu_release_infos():
-    data = request_github_api('https://api.github.com/repos/pineappleEA/pineapple-src/releases')
-    res = [item for item in data if item['author']['login'] == 'pineappleEA']
+    repo, data = _request_release_list('ea')
+    res = [item for item in data if _is_ea_release(repo, item)]
     return res
 
 
-def get_all_yuzu_release_versions(branch: str):
+def get_all_yuzu_release_versions(branch: str, limit=None, stable_only=False, since=None, until=None):
+    from utils.common import filter_release_infos
     res = []
+    repo, data = _request_release_list(branch)
+    if branch.lower() != 'mainline':
+        data = [item for item in data if _is_ea_release(repo, item)]
+    data = filter_release_infos(data, limit, stable_only, since, until)
     if branch.lower() == 'mainline':
-        data = request_github_api('https://api.github.com/repos/yuzu-emu/yuzu-mainline/releases')
         for item in data:
             res.append(item['tag_name'][11:])
     else:
-        data = request_github_api('https://api.github.com/repos/pineappleEA/pineapple-src/releases')
         for item in data:
-            if item['author']['login'] == 'pineappleEA':
-                res.append(item['tag_name'][3:])
+            res.append(item['tag_name'][3:])
     return res
 
 
@@ -26,11 +60,17 @@ def get_latest_yuzu_release_info():
 
 
 def get_yuzu_release_info_by_version(version, branch='ea'):
-    if branch.lower() == 'mainline':
-        url = f'https://api.github.com/repos/yuzu-emu/yuzu-mainline/releases/tags/mainline-0-{version}'
-    else:
-        url = f'https://api.github.com/repos/pineappleEA/pineapple-src/releases/tags/EA-{version}'
-    return request_github_api(url)
+    tag = f'mainline-0-{version}' if branch.lower() == 'mainline' else f'EA-{version}'
+    data = {}
+    for repo in get_yuzu_repo_candidates(branch):
+        try:
+            data = request_github_api(f'https://api.github.com/repos/{repo}/releases/tags/{tag}')
+            if data.get('tag_name'):
+                return data
+            logger.warning(f'release [{tag}] not found in repo [{repo}], response: {data}')
+        except Exception as e:
+            logger.warning(f'fail to get release [{tag}] from repo [{repo}], msg: {str(e)}')
+    return data
 
 
 if __name__ == '__main__':
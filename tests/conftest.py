@@ -0,0 +1,8 @@
+import os
+import sys
+import tempfile
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).parent.parent))
+# config and logging create files in the working directory when imported
+os.chdir(tempfile.mkdtemp(prefix='ns-emu-tools-test-'))
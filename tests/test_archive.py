@@ -0,0 +1,39 @@
+import zipfile
+
+import pytest
+
+from utils.archive import classify_extract_error, extract_zip, is_safe_member
+
+
+def test_is_safe_member(tmp_path):
+    assert is_safe_member(tmp_path, 'a/b.txt')
+    assert not is_safe_member(tmp_path, '../evil.txt')
+    assert not is_safe_member(tmp_path, '..\\evil.txt')
+    assert not is_safe_member(tmp_path, 'a/../../evil.txt')
+
+
+def test_extract_zip_skips_unsafe_members(tmp_path):
+    zip_path = tmp_path.joinpath('test.zip')
+    with zipfile.ZipFile(zip_path, 'w') as zf:
+        zf.writestr('ok/a.txt', 'ok')
+        zf.writestr('../evil.txt', 'evil')
+        zf.writestr('..\\evil2.txt', 'evil')
+    target = tmp_path.joinpath('out')
+    extract_zip(zip_path, target)
+    assert target.joinpath('ok', 'a.txt').read_text() == 'ok'
+    assert not tmp_path.joinpath('evil.txt').exists()
+    assert not tmp_path.joinpath('evil2.txt').exists()
+    assert [p.name for p in target.iterdir()] == ['ok']
+
+
+def test_extract_corrupted_zip(tmp_path):
+    zip_path = tmp_path.joinpath('broken.zip')
+    zip_path.write_bytes(b'PK\x03\x04 not a zip')
+    with pytest.raises(RuntimeError, match='已损坏'):
+        extract_zip(zip_path, tmp_path.joinpath('out'))
+
+
+def test_classify_extract_error(tmp_path):
+    assert '已损坏' in classify_extract_error(zipfile.BadZipFile(), 'a.zip', tmp_path)
+    assert '权限' in classify_extract_error(PermissionError(), 'a.zip', tmp_path)
+    assert classify_extract_error(ValueError('boom'), 'a.zip', tmp_path) is None
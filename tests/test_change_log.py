@@ -0,0 +1,11 @@
+from utils.common import normalize_change_log, split_change_log_by_version
+
+
+def test_normalize_change_log():
+    change_log = '<!-- comment -->\r\n# Changelog\n\n\n\n## 1.2\n<b>fix</b> a\n## 1.1\nfix b\n'
+    assert normalize_change_log(change_log) == '# Changelog\n\n## 1.2\nfix a\n## 1.1\nfix b'
+    assert normalize_change_log(change_log, 1) == '# Changelog\n\n## 1.2\nfix a'
+
+
+def test_split_change_log_without_versions():
+    assert split_change_log_by_version('just text') == ('just text', [])
@@ -0,0 +1,35 @@
+from module.cheats import parse_cheat_file_with_warnings
+
+
+def test_parse_cheat_file(tmp_path):
+    path = tmp_path.joinpath('0123456789ABCDEF.txt')
+    path.write_text('{Master Code}\n580F0000 01234567\n\n[A]\n04000000 00000000 00000001\n', encoding='utf-8-sig')
+    cheats, warnings = parse_cheat_file_with_warnings(path)
+    assert cheats == {'{Master Code}': '580F0000 01234567', 'A': '04000000 00000000 00000001'}
+    assert warnings == []
+
+
+def test_parse_gbk_cheat_file(tmp_path):
+    path = tmp_path.joinpath('0123456789ABCDEF.txt')
+    path.write_bytes('[无限金钱]\n04000000 00000000 00000001\n'.encode('gbk'))
+    cheats, warnings = parse_cheat_file_with_warnings(path)
+    assert cheats == {'无限金钱': '04000000 00000000 00000001'}
+    assert len(warnings) == 1
+
+
+def test_parse_malformed_cheat_file(tmp_path):
+    path = tmp_path.joinpath('0123456789ABCDEF.txt')
+    path.write_bytes(b'\xff\xfe04000000\n[A]\n04000000 00000000 00000001\nbad$line\n[B]\n')
+    cheats, warnings = parse_cheat_file_with_warnings(path)
+    assert cheats == {'A': '04000000 00000000 00000001', 'B': ''}
+    assert any('bad$line' in warning for warning in warnings)
+    assert any('[B]' in warning for warning in warnings)
+
+
+def test_parse_cheat_file_with_bad_header(tmp_path):
+    path = tmp_path.joinpath('0123456789ABCDEF.txt')
+    path.write_text('[A]\n04000000 00000000 00000001\n[坏块\n04000000 00000000 00000002\n[B]\n'
+                    '04000000 00000000 00000003\n', encoding='utf-8')
+    cheats, warnings = parse_cheat_file_with_warnings(path)
+    assert cheats == {'A': '04000000 00000000 00000001', 'B': '04000000 00000000 00000003'}
+    assert any('[坏块' in warning for warning in warnings)
@@ -0,0 +1,26 @@
+from utils.common import format_bytes, format_eta
+
+
+def test_format_bytes_boundaries():
+    assert format_bytes(0) == '0 B'
+    assert format_bytes(1023) == '1023 B'
+    assert format_bytes(1024) == '1.00 KiB'
+    assert format_bytes(1024 ** 2 - 1) == '1024.00 KiB'
+    assert format_bytes(1024 ** 4) == '1.00 TiB'
+    assert format_bytes(1024 ** 5) == '1.00 PiB'
+    assert format_bytes(3 * 1024 ** 6) == '3072.00 PiB'
+
+
+def test_format_bytes_invalid_size():
+    assert format_bytes(None) == '0 B'
+    assert format_bytes(-1) == '0 B'
+
+
+def test_format_eta():
+    assert format_eta(0) == '0s'
+    assert format_eta(None) == '0s'
+    assert format_eta(59) == '59s'
+    assert format_eta(60) == '1m00s'
+    assert format_eta(3599) == '59m59s'
+    assert format_eta(3600) == '1h00m00s'
+    assert format_eta(90061.7) == '25h01m01s'
@@ -0,0 +1,31 @@
+from module.keys import classify_keys_file
+
+
+def test_classify_prod_keys(tmp_path):
+    path = tmp_path.joinpath('prod.keys')
+    path.write_text('; dumped by lockpick\n\nheader_key = 00ff\nmaster_key_00 = 11aa\n')
+    assert classify_keys_file(path) == 'prod_keys'
+
+
+def test_classify_title_keys(tmp_path):
+    path = tmp_path.joinpath('title.keys')
+    path.write_text('0100000000000000000000000000000a = 00112233445566778899aabbccddeeff\n')
+    assert classify_keys_file(path) == 'title_keys'
+
+
+def test_classify_non_keys_files(tmp_path):
+    empty = tmp_path.joinpath('empty.keys')
+    empty.write_text('; only comment\n\n')
+    assert classify_keys_file(empty) == 'empty'
+    text = tmp_path.joinpath('readme.keys')
+    text.write_text('put your keys here\n')
+    assert classify_keys_file(text) == 'invalid'
+    archive = tmp_path.joinpath('keys.zip')
+    archive.write_bytes(b'PK\x03\x04' + b'\x00' * 16)
+    assert classify_keys_file(archive) == 'archive'
+
+
+def test_classify_keys_file_with_bom(tmp_path):
+    path = tmp_path.joinpath('prod.keys')
+    path.write_text('header_key = 00ff\nmaster_key_00 = 11aa\n', encoding='utf-8-sig')
+    assert classify_keys_file(path) == 'prod_keys'
@@ -0,0 +1,20 @@
+from utils.common import filter_release_infos
+
+releases = [
+    {'tag_name': '3', 'prerelease': True, 'published_at': '2023-03-01T00:00:00Z'},
+    {'tag_name': '2', 'prerelease': False, 'published_at': '2023-02-01T00:00:00Z'},
+    {'tag_name': '1', 'prerelease': False, 'draft': True, 'published_at': '2023-01-01T00:00:00Z'},
+    {'tag_name': '0', 'prerelease': False, 'published_at': None},
+]
+
+
+def _tags(res):
+    return [item['tag_name'] for item in res]
+
+
+def test_filter_release_infos():
+    assert _tags(filter_release_infos(releases)) == ['3', '2', '1', '0']
+    assert _tags(filter_release_infos(releases, limit=2)) == ['3', '2']
+    assert _tags(filter_release_infos(releases, stable_only=True)) == ['2', '0']
+    assert _tags(filter_release_infos(releases, since='2023-02-01')) == ['3', '2', '0']
+    assert _tags(filter_release_infos(releases, until='2023-02-01')) == ['2', '1', '0']
@@ -57,9 +57,13 @@ def main(port=0, mode=None, dev=False):
     eel.init('vue/public') if dev else eel.init("web")
     shutdown_delay = 114514 if dev else 1
     logger.info('eel init finished.')
-    from module.msg_notifier import update_notifier
+    from module.msg_notifier import update_notifier, eel_download_event_forwarder
     default_page = f'index.html'
     update_notifier('eel-console')
+    from module.downloader import add_download_listener
+    add_download_listener(eel_download_event_forwarder)
+    from module.msg_notifier import watch_config_changes
+    eel.spawn(watch_config_changes)
     if mode is None:
         if can_use_chrome():
             mode = 'chrome'
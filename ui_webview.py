@@ -10,6 +10,10 @@ import webview
 logger = logging.getLogger(__name__)
 default_page = f'index.html'
 port = 0
+webview2_client_id = '{F3017226-FE2A-4295-8BDF-00C3A9A7E4C5}'
+webview2_bootstrapper_name = 'MicrosoftEdgeWebview2Setup.exe'
+webview2_bootstrapper_url = 'https://go.microsoft.com/fwlink/p/?LinkId=2124703'
+webview2_offline_download_page = 'https://developer.microsoft.com/zh-cn/microsoft-edge/webview2/#download-section'
 
 
 def import_api_modules():
@@ -17,6 +21,9 @@ def import_api_modules():
 
 
 def start_eel():
+    # eel runs in the webview worker thread, so the watcher has to be spawned on the same hub
+    from module.msg_notifier import watch_config_changes
+    eel.spawn(watch_config_changes)
     eel.start(default_page, port=port, mode=False)
 
 
@@ -27,14 +34,83 @@ def close_all_windows():
             win.destroy()
 
 
+def is_webview2_installed():
+    import winreg
+    key_paths = [
+        (winreg.HKEY_LOCAL_MACHINE, rf'SOFTWARE\WOW6432Node\Microsoft\EdgeUpdate\Clients\{webview2_client_id}'),
+        (winreg.HKEY_LOCAL_MACHINE, rf'SOFTWARE\Microsoft\EdgeUpdate\Clients\{webview2_client_id}'),
+        (winreg.HKEY_CURRENT_USER, rf'Software\Microsoft\EdgeUpdate\Clients\{webview2_client_id}'),
+    ]
+    for root, key_path in key_paths:
+        try:
+            with winreg.OpenKey(root, key_path, 0, winreg.KEY_READ) as key:
+                version: str = winreg.QueryValueEx(key, 'pv')[0]
+                if version and version != '0.0.0.0':
+                    logger.info(f'WebView2 version: {version}')
+                    return True
+        except OSError:
+            continue
+    return False
+
+
+def find_local_webview2_bootstrapper():
+    import sys
+    from pathlib import Path
+    for folder in (Path(sys.argv[0]).parent, Path('.')):
+        bootstrapper = folder.joinpath(webview2_bootstrapper_name)
+        if bootstrapper.exists():
+            return bootstrapper
+
+
+def download_webview2_bootstrapper():
+    import tempfile
+    from pathlib import Path
+    from utils.network import stream_download
+    bootstrapper = Path(tempfile.gettempdir()).joinpath(webview2_bootstrapper_name)
+    try:
+        logger.info(f'downloading WebView2 bootstrapper from {webview2_bootstrapper_url}')
+        return stream_download(webview2_bootstrapper_url, bootstrapper)
+    except Exception as e:
+        logger.warning(f'fail to download WebView2 bootstrapper, msg: {str(e)}')
+
+
+def install_webview2(bootstrapper):
+    import subprocess
+    logger.info(f'installing WebView2 with {bootstrapper}')
+    process = subprocess.run([str(bootstrapper), '/silent', '/install'])
+    logger.info(f'WebView2 installer exit with code: {process.returncode}')
+    return is_webview2_installed()
+
+
+def check_and_install_webview2():
+    if is_webview2_installed():
+        return
+    logger.info('WebView2 runtime not found.')
+    bootstrapper = find_local_webview2_bootstrapper()
+    if bootstrapper and install_webview2(bootstrapper):
+        return
+    bootstrapper = download_webview2_bootstrapper()
+    if bootstrapper and install_webview2(bootstrapper):
+        return
+    msg = f'未能安装 WebView2 运行时, 请从 {webview2_offline_download_page} 下载离线安装包手动安装, ' \
+          f'或将 {webview2_bootstrapper_name} 放在程序所在目录后重新启动.'
+    logger.error(msg)
+    import ctypes
+    ctypes.windll.user32.MessageBoxW(0, msg, 'NS EMU TOOLS', 0x10)
+    raise RuntimeError(msg)
+
+
 def main():
     global port
+    check_and_install_webview2()
     import_api_modules()
     logger.info('eel init starting...')
     eel.init('vue/public') if port else eel.init("web")
     logger.info('eel init finished.')
-    from module.msg_notifier import update_notifier
+    from module.msg_notifier import update_notifier, eel_download_event_forwarder
     update_notifier('eel-console')
+    from module.downloader import add_download_listener
+    add_download_listener(eel_download_event_forwarder)
     if port == 0:
         from utils.network import get_available_port
         port = get_available_port()
@@ -0,0 +1,86 @@
+import errno
+import os
+import logging
+import zipfile
+from pathlib import Path
+
+
+logger = logging.getLogger(__name__)
+
+
+def is_safe_member(target_dir, member_name: str):
+    target = os.path.abspath(target_dir)
+    member_path = os.path.abspath(os.path.join(target, member_name.replace('\\', '/')))
+    return os.path.commonpath([target, member_path]) == target
+
+
+def filter_safe_members(target_dir, member_names):
+    res = []
+    for name in member_names:
+        if is_safe_member(target_dir, name):
+            res.append(name)
+        else:
+            logger.warning(f'skip unsafe archive member: {name}')
+    return res
+
+
+def is_corrupted_archive_error(e: Exception):
+    # py7zr raises its own exception types (Bad7zFile, CrcError, ...) for broken archives
+    return isinstance(e, (zipfile.BadZipFile, EOFError)) or type(e).__module__.startswith('py7zr')
+
+
+def classify_extract_error(e: Exception, file_path, target_dir):
+    """
+    :return: user facing reason of the error, None if the error is not recognized
+    """
+    if isinstance(e, OSError) and (e.errno == errno.ENOSPC or getattr(e, 'winerror', None) in (39, 112)):
+        return f'磁盘空间不足, 无法解压至 {target_dir}'
+    if isinstance(e, PermissionError) or (isinstance(e, OSError) and e.errno in (errno.EACCES, errno.EPERM)):
+        return f'没有权限写入目录 {target_dir}, 请检查目录权限或以管理员身份运行'
+    if isinstance(e, FileNotFoundError) and not Path(file_path).exists():
+        return f'压缩包 {file_path} 不存在'
+    if is_corrupted_archive_error(e):
+        return f'压缩包 {file_path} 已损坏或格式不正确, 请删除后重新下载'
+    return None
+
+
+def _extract(file_path, target_dir, extract_func):
+    try:
+        Path(target_dir).mkdir(parents=True, exist_ok=True)
+        extract_func()
+    except Exception as e:
+        msg = classify_extract_error(e, file_path, target_dir)
+        if msg:
+            logger.warning(f'fail to extract {file_path} to {target_dir}, reason: {msg}, error: {str(e)}')
+        else:
+            logger.exception(f'fail to extract {file_path} to {target_dir}')
+            msg = f'解压 {file_path} 时发生未知错误: {str(e)}'
+        raise RuntimeError(msg) from e
+
+
+def extract_zip(file_path, target_dir):
+    def extract_func():
+        with zipfile.ZipFile(file_path, 'r') as zf:
+            members = filter_safe_members(target_dir, zf.namelist())
+            zf.extractall(str(target_dir), members)
+    _extract(file_path, target_dir, extract_func)
+
+
+def extract_7z(file_path, target_dir):
+    import py7zr
+
+    def extract_func():
+        with py7zr.SevenZipFile(file_path, 'r') as zf:
+            members = filter_safe_members(target_dir, zf.getnames())
+            zf.extract(str(target_dir), targets=members)
+    _extract(file_path, target_dir, extract_func)
+
+
+def uncompress(file_path, target_dir):
+    name = Path(file_path).name.lower()
+    if name.endswith('.zip'):
+        extract_zip(file_path, target_dir)
+    elif name.endswith('.7z'):
+        extract_7z(file_path, target_dir)
+    else:
+        raise RuntimeError(f'Unknown file format: {file_path}')
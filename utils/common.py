@@ -1,7 +1,19 @@
+import os
 import re
+from pathlib import Path, PureWindowsPath
 
 
-path_unicode_re = re.compile(r'\\x([\da-z]{4})')
+qt_escape_re = re.compile(r'\\(x[\da-fA-F]{4}|[\\"\'])')
+invalid_path_chars = set('<>:"|?*')
+reserved_path_names = {'CON', 'PRN', 'AUX', 'NUL', *(f'COM{i}' for i in range(1, 10)),
+                       *(f'LPT{i}' for i in range(1, 10))}
+max_path_length = 260
+html_comment_re = re.compile(r'<!--.*?-->', re.DOTALL)
+html_tag_re = re.compile(r'<(?!https?://)/?[a-zA-Z][^>]*>')
+multi_new_line_re = re.compile(r'\n{3,}')
+markdown_heading_re = re.compile(r'^(#{1,6})\s+(.+?)\s*#*$')
+version_re = re.compile(r'\d+\.\d+')
+max_change_log_length = 50000
 
 
 def callback(hwnd, strings):
@@ -13,6 +25,19 @@ def callback(hwnd, strings):
     return True
 
 
+def popen_hidden(args, **kwargs):
+    """
+    start a background process without showing console window on Windows
+    """
+    import subprocess
+    if os.name == 'nt':
+        st_inf = kwargs.get('startupinfo') or subprocess.STARTUPINFO()
+        st_inf.dwFlags = st_inf.dwFlags | subprocess.STARTF_USESHOWWINDOW
+        kwargs['startupinfo'] = st_inf
+        kwargs['creationflags'] = kwargs.get('creationflags', 0) | subprocess.CREATE_NO_WINDOW
+    return subprocess.Popen(args, **kwargs)
+
+
 def get_all_window_name():
     from win32 import win32gui
     win_list = []  # list of strings containing win handles and window titles
@@ -20,7 +45,196 @@ def get_all_window_name():
     return win_list
 
 
+def _unescape_qt_char(match):
+    escaped = match.group(1)
+    if escaped.startswith('x') and len(escaped) == 5:
+        return chr(int(escaped[1:], 16))
+    return escaped
+
+
 def escape_yuzu_path(raw_path_in_config: str):
-    raw_path_in_config = raw_path_in_config.replace("'", "\'")
-    raw_path_in_config = path_unicode_re.sub(r'\\u\1', raw_path_in_config)
-    return eval(f"'{raw_path_in_config}'")
+    raw_path_in_config = raw_path_in_config.strip()
+    if len(raw_path_in_config) > 1 and raw_path_in_config[0] == raw_path_in_config[-1] == '"':
+        raw_path_in_config = raw_path_in_config[1:-1]
+    return qt_escape_re.sub(_unescape_qt_char, raw_path_in_config)
+
+
+def validate_install_path(path_str: str):
+    """
+    check whether the given path can be used as install path
+    :param path_str: path input by user
+    :return: absolute path
+    """
+    if not path_str or not path_str.strip():
+        raise RuntimeError('路径不能为空.')
+    win_path = PureWindowsPath(path_str.strip())
+    if win_path.drive.startswith('\\\\'):
+        raise RuntimeError(f'不支持使用网络路径: {path_str}')
+    for part in win_path.parts[1:] if win_path.anchor else win_path.parts:
+        if any(ch in invalid_path_chars or ord(ch) < 32 for ch in part):
+            raise RuntimeError(f'路径中包含非法字符: {part}')
+        if part.split('.')[0].upper() in reserved_path_names:
+            raise RuntimeError(f'路径中包含系统保留名称: {part}')
+        if part != part.rstrip(' .'):
+            raise RuntimeError(f'路径中的目录名不能以空格或点结尾: {part}')
+    path = Path(path_str.strip()).absolute()
+    if len(str(path)) >= max_path_length:
+        raise RuntimeError(f'路径过长 ({len(str(path))} 个字符), 请选择更短的路径.')
+    existing_parent = path
+    while not existing_parent.exists() and existing_parent.parent != existing_parent:
+        existing_parent = existing_parent.parent
+    if not existing_parent.is_dir() or not os.access(existing_parent, os.W_OK):
+        raise RuntimeError(f'没有目录 {existing_parent} 的写入权限.')
+    return path
+
+
+def is_long_paths_enabled():
+    if os.name != 'nt':
+        return True
+    try:
+        import winreg
+        with winreg.OpenKey(winreg.HKEY_LOCAL_MACHINE, r'SYSTEM\CurrentControlSet\Control\FileSystem') as key:
+            return winreg.QueryValueEx(key, 'LongPathsEnabled')[0] == 1
+    except OSError:
+        return False
+
+
+def get_longest_target_path_length(src_dir: Path, target_dir: Path):
+    """
+    length of the longest file path after copying files in src_dir to target_dir
+    """
+    target_length = len(str(Path(target_dir).absolute()))
+    longest = target_length
+    for path in Path(src_dir).rglob('*'):
+        longest = max(longest, target_length + 1 + len(str(path.relative_to(src_dir))))
+    return longest
+
+
+def check_target_path_length(src_dir: Path, target_dir: Path):
+    longest = get_longest_target_path_length(src_dir, target_dir)
+    if longest < max_path_length or is_long_paths_enabled():
+        return
+    raise RuntimeError(f'安装后的文件路径最长将达到 {longest} 个字符, '
+                       f'超出 Windows 的路径长度限制 ({max_path_length}). 请选择更短的安装路径, 或在注册表 '
+                       f'HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\FileSystem 中'
+                       f'将 LongPathsEnabled 设置为 1 以启用长路径支持, 然后重启电脑.')
+
+
+def split_change_log_by_version(change_log: str):
+    """
+    split markdown change log into sections by version headings
+    :return: preface text and list of (version title, section text)
+    """
+    lines = change_log.split('\n')
+    version_level = None
+    for line in lines:
+        match = markdown_heading_re.match(line)
+        if match and version_re.search(match.group(2)):
+            level = len(match.group(1))
+            version_level = level if version_level is None else min(version_level, level)
+    if version_level is None:
+        return change_log, []
+    preface = []
+    sections = []
+    for line in lines:
+        match = markdown_heading_re.match(line)
+        if match and len(match.group(1)) == version_level and version_re.search(match.group(2)):
+            sections.append((match.group(2), [line]))
+        elif sections:
+            sections[-1][1].append(line)
+        else:
+            preface.append(line)
+    return '\n'.join(preface), [(title, '\n'.join(section)) for title, section in sections]
+
+
+def normalize_change_log(change_log: str, max_versions=None):
+    change_log = change_log.replace('\r\n', '\n').replace('\r', '\n')
+    change_log = html_comment_re.sub('', change_log)
+    change_log = html_tag_re.sub('', change_log)
+    change_log = multi_new_line_re.sub('\n\n', change_log).strip()
+    if max_versions:
+        preface, sections = split_change_log_by_version(change_log)
+        if sections:
+            change_log = '\n'.join([preface] + [section for _, section in sections[:max_versions]]).strip()
+    if len(change_log) > max_change_log_length:
+        change_log = change_log[:max_change_log_length] + '\n\n...'
+    return change_log
+
+
+def format_bytes(size) -> str:
+    size = max(0, int(size or 0))
+    for unit in ('B', 'KiB', 'MiB', 'GiB', 'TiB'):
+        if size < 1024:
+            return f'{size} {unit}' if unit == 'B' else f'{size:.2f} {unit}'
+        size /= 1024
+    return f'{size:.2f} PiB'
+
+
+def format_eta(seconds) -> str:
+    seconds = max(0, int(seconds or 0))
+    hours, rest = divmod(seconds, 3600)
+    minutes, seconds = divmod(rest, 60)
+    if hours:
+        return f'{hours}h{minutes:02d}m{seconds:02d}s'
+    if minutes:
+        return f'{minutes}m{seconds:02d}s'
+    return f'{seconds}s'
+
+
+def filter_release_infos(releases, limit=None, stable_only=False, since=None, until=None):
+    """
+    filter github release infos
+    :param releases: release infos from github api
+    :param limit: max count of result
+    :param stable_only: exclude prerelease and draft
+    :param since: only keep release published at or after the date, e.g. 2023-01-01
+    :param until: only keep release published at or before the date, e.g. 2023-12-31
+    :return: filtered release infos
+    """
+    res = []
+    for item in releases:
+        if stable_only and (item.get('prerelease') or item.get('draft')):
+            continue
+        published_date = (item.get('published_at') or '')[:10]
+        if since and published_date and published_date < since:
+            continue
+        if until and published_date and published_date > until:
+            continue
+        res.append(item)
+        if limit and len(res) >= limit:
+            break
+    return res
+
+
+def open_folder(folder, create=False):
+    """
+    open folder in system file explorer
+    :param folder: folder path
+    :param create: create the folder if not exists, otherwise raise error
+    """
+    import subprocess
+    folder = Path(folder).absolute()
+    if not folder.exists():
+        if not create:
+            raise RuntimeError(f'目录 {folder} 不存在.')
+        folder.mkdir(parents=True, exist_ok=True)
+    if not folder.is_dir():
+        raise RuntimeError(f'{folder} 不是一个目录.')
+    # pass args as list so that the path would not be parsed as extra arguments
+    subprocess.Popen(['explorer', str(folder)])
+    return str(folder)
+
+
+def is_valid_http_url(url: str):
+    from urllib.parse import urlparse
+    if not isinstance(url, str):
+        return False
+    parsed = urlparse(url.strip())
+    return parsed.scheme in ('http', 'https') and bool(parsed.netloc)
+
+
+def open_url(url: str):
+    import webbrowser
+    if not is_valid_http_url(url):
+        raise RuntimeError(f'不支持打开该链接: {url}')
+    webbrowser.open(url.strip(), new=0, autoraise=True)
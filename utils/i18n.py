@@ -0,0 +1,138 @@
+from config import config
+
+
+default_language = 'zh'
+string_table = {
+    'zh': {
+        'emu_name.yuzu': 'yuzu',
+        'emu_name.ryujinx': 'Ryujinx',
+        'step.download_firmware': '下载固件',
+        'step.unzip_firmware': '解压安装固件',
+        'step.download_emu': '下载 {emu}',
+        'step.finish_install': '完成安装',
+        'step.unzip_emu': '解压 {emu} 文件',
+        'step.install_emu_files': '安装 {emu} 文件至目录',
+        'install.pause_requested': '安装流程将在当前步骤完成后暂停',
+        'install.paused': '安装已暂停, 恢复后将继续: {step}',
+        'install.resumed': '安装已恢复',
+        'download.cancelled': '下载已取消',
+        'download.stalled': '下载长时间无进展, 请检查网络后重试',
+        'download.stall_retry': '下载长时间无进展, 正在重试 ({count}/{total})',
+        'download.network_waiting': '网络连接中断, 正在等待网络恢复...',
+        'download.network_recovered': '网络已恢复, 继续下载',
+        'download.network_timeout': '等待网络恢复超时',
+        'install.fetching_version': '正在获取 {emu} 版本信息...',
+        'install.release_not_found': '无法获取 {branch} 分支的 [{version}] 版本信息',
+        'install.download_url_failed': '获取 {emu} 下载链接失败',
+        'install.using_cached_package': '使用已下载的 {emu} 安装包: {path}',
+        'install.start_download': '开始下载 {emu}...',
+        'install.unzipping': '正在解压 {emu} 文件...',
+        'install.copying_files': '安装 {emu} 文件至目录...',
+        'install.same_version': '当前就是 {branch} [{version}] 版本的 {emu}, 跳过安装.',
+        'install.success': '{emu} {branch} [{version}] 安装成功, 安装目录: {path}',
+        'firmware.fetching_info': '正在获取固件信息...',
+        'firmware.not_found': '未找到固件版本 [{version}], 跳过安装.',
+        'firmware.start_download': '开始下载固件...',
+        'firmware.unzipping': '开始解压安装固件...',
+        'firmware.same_version': '当前的固件就是 [{version}], 跳过安装.',
+        'firmware.success': '固件 [{version}] 安装成功, 请安装相应的 key 至 {emu}.',
+        'firmware.install_failed': '固件 [{version}] 安装失败',
+        'download.start': '正在从 {source} 下载',
+        'download.file_exists': '文件已存在, 跳过下载.',
+        'download.switch_mirror': '从 {source} 下载失败, 尝试切换至 {next_source}',
+        'download.failed': '下载出错, error_code: {code}, error message: {message}',
+        'download.finished': '下载完成, 文件: {path}, 大小: {size}, 耗时: {duration}',
+        'download.file_missing': '下载已完成但未找到文件: {path}',
+        'download.size_mismatch': '下载的文件大小不一致, 预期: {expected}, 实际: {actual}',
+        'setup.unsupported_emu': '不支持的模拟器类型: {emu}',
+        'setup.step_install_emu': '安装 {emu} {branch} [{version}]',
+        'setup.step_install_firmware': '安装固件 [{version}]',
+        'setup.step_check_keys': '检查 keys',
+        'setup.prod_keys_missing': '未检测到 prod.keys',
+        'setup.step_running': '[{index}/{total}] {name}...',
+        'setup.step_aborted': '[{index}/{total}] {name} 失败, 一键配置中止: {error}',
+        'setup.failed': '一键配置在步骤 [{name}] 失败: {error}',
+        'setup.step_not_passed': '[{index}/{total}] {name} 未通过: {error}',
+        'setup.finished': '{emu} 一键配置完成',
+        'verify.incomplete': '{emu} 安装不完整, 缺失文件: {missing}, 损坏文件: {corrupted}, 建议重新安装',
+        'guidance.install_emulator': '尚未安装 {emu}, 请先安装模拟器',
+        'guidance.install_firmware': '尚未安装固件, 请先安装固件',
+        'guidance.install_prod_keys': '未检测到 prod.keys, 游戏无法运行, 请将与固件版本对应的 prod.keys 放至 {path}',
+        'guidance.install_title_keys': '未检测到 title.keys, 部分游戏可能需要, 可放至 {path}',
+        'guidance.ready': '模拟器、固件与 keys 均已就绪',
+    },
+    'en': {
+        'emu_name.yuzu': 'yuzu',
+        'emu_name.ryujinx': 'Ryujinx',
+        'step.download_firmware': 'Download firmware',
+        'step.unzip_firmware': 'Extract and install firmware',
+        'step.download_emu': 'Download {emu}',
+        'step.finish_install': 'Finish installation',
+        'step.unzip_emu': 'Extract {emu} files',
+        'step.install_emu_files': 'Copy {emu} files to install folder',
+        'install.pause_requested': 'Installation will pause after the current step',
+        'install.paused': 'Installation paused, will continue with: {step}',
+        'install.resumed': 'Installation resumed',
+        'download.cancelled': 'Download cancelled',
+        'download.stalled': 'Download made no progress for a long time, please check your network and retry',
+        'download.stall_retry': 'Download made no progress for a long time, retrying ({count}/{total})',
+        'download.network_waiting': 'Network disconnected, waiting for it to recover...',
+        'download.network_recovered': 'Network recovered, resuming download',
+        'download.network_timeout': 'Timed out waiting for network to recover',
+        'install.fetching_version': 'Fetching {emu} version info...',
+        'install.release_not_found': 'Unable to get version info of [{version}] on branch {branch}',
+        'install.download_url_failed': 'Failed to get {emu} download url',
+        'install.using_cached_package': 'Using downloaded {emu} package: {path}',
+        'install.start_download': 'Start downloading {emu}...',
+        'install.unzipping': 'Extracting {emu} files...',
+        'install.copying_files': 'Copying {emu} files to install folder...',
+        'install.same_version': '{emu} {branch} [{version}] is already installed, skip installation.',
+        'install.success': '{emu} {branch} [{version}] installed successfully, install folder: {path}',
+        'firmware.fetching_info': 'Fetching firmware info...',
+        'firmware.not_found': 'Firmware version [{version}] not found, skip installation.',
+        'firmware.start_download': 'Start downloading firmware...',
+        'firmware.unzipping': 'Extracting and installing firmware...',
+        'firmware.same_version': 'Firmware [{version}] is already installed, skip installation.',
+        'firmware.success': 'Firmware [{version}] installed successfully, please install matching keys to {emu}.',
+        'firmware.install_failed': 'Failed to install firmware [{version}]',
+        'download.start': 'Downloading from {source}',
+        'download.file_exists': 'File already exists, skip download.',
+        'download.switch_mirror': 'Download from {source} failed, switching to {next_source}',
+        'download.failed': 'Download failed, error_code: {code}, error message: {message}',
+        'download.finished': 'Download finished, file: {path}, size: {size}, duration: {duration}',
+        'download.file_missing': 'Download completed but file not found: {path}',
+        'download.size_mismatch': 'Downloaded file size mismatch, expected: {expected}, actual: {actual}',
+        'setup.unsupported_emu': 'Unsupported emulator type: {emu}',
+        'setup.step_install_emu': 'Install {emu} {branch} [{version}]',
+        'setup.step_install_firmware': 'Install firmware [{version}]',
+        'setup.step_check_keys': 'Check keys',
+        'setup.prod_keys_missing': 'prod.keys not found',
+        'setup.step_running': '[{index}/{total}] {name}...',
+        'setup.step_aborted': '[{index}/{total}] {name} failed, setup aborted: {error}',
+        'setup.failed': 'Setup failed at step [{name}]: {error}',
+        'setup.step_not_passed': '[{index}/{total}] {name} not passed: {error}',
+        'setup.finished': '{emu} setup finished',
+        'verify.incomplete': '{emu} installation is incomplete, missing files: {missing}, '
+                             'corrupted files: {corrupted}, please reinstall',
+        'guidance.install_emulator': '{emu} is not installed, please install the emulator first',
+        'guidance.install_firmware': 'Firmware is not installed, please install firmware first',
+        'guidance.install_prod_keys': 'prod.keys not found, games cannot run, please put the prod.keys '
+                                      'matching the firmware version to {path}',
+        'guidance.install_title_keys': 'title.keys not found, some games may need it, you can put it to {path}',
+        'guidance.ready': 'Emulator, firmware and keys are all ready',
+    },
+}
+
+
+def get_language():
+    language = config.setting.ui.language
+    return language if language in string_table else default_language
+
+
+def tr(key: str, **kwargs):
+    text = string_table[get_language()].get(key) or string_table[default_language].get(key, key)
+    return text.format(**kwargs) if kwargs else text
+
+
+def get_emu_name(emu_type: str):
+    return tr(f'emu_name.{emu_type}')
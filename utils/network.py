@@ -6,8 +6,9 @@ import requests_cache
 
 logger = logging.getLogger(__name__)
 
+firmware_base_url = 'https://archive.org/download/nintendo-switch-global-firmwares/'
 url_override_map = {
-    'https://archive.org/download/nintendo-switch-global-firmwares/': 'https://nsarchive.e6ex.com/nsfrp/',
+    firmware_base_url: 'https://nsarchive.e6ex.com/nsfrp/',
     'https://api.github.com': 'https://cfrp.e6ex.com/ghapi',
     # 'https://aka.ms/vs': 'https://nsarchive.e6ex.com/msvc'
     'https://raw.githubusercontent.com': 'https://www.githubs.cn/raw-githubusercontent',
@@ -33,7 +34,26 @@ options_on_cdn = {
     'min-split-size': '12M',
 }
 
+# mirrors and cdn may limit rate or ban ip on too many connections
+max_connections_on_mirror = 4
+max_connections_on_origin = 16
+
+doh_servers = {
+    'alidns': 'https://dns.alidns.com/resolve',
+    'cloudflare': 'https://cloudflare-dns.com/dns-query',
+}
+
 github_api_fallback_flag = False
+remote_file_validator_cache_ttl = 60
+remote_file_validator_cache = {}
+github_api_rate_low_watermark = 5
+github_api_rate_status = {
+    'request_count': 0,
+    'limit': None,
+    'remaining': None,
+    'reset': None,
+    'last_limited_time': None,
+}
 
 
 def is_using_proxy():
@@ -50,8 +70,25 @@ def get_proxy_option():
     return {'all-proxy': iter(get_proxies().values()).__next__()}
 
 
+def is_proxy_available(proxy_url: str, timeout=3):
+    import socket
+    from urllib.parse import urlparse
+    parsed = urlparse(proxy_url if '://' in proxy_url else f'http://{proxy_url}')
+    try:
+        port = parsed.port or (443 if parsed.scheme == 'https' else 80)
+        with socket.create_connection((parsed.hostname, port), timeout=timeout):
+            return True
+    except (OSError, ValueError) as e:
+        logger.warning(f'proxy {proxy_url} is not available, msg: {str(e)}')
+        return False
+
+
 def get_global_options():
-    return {}
+    download_setting = config.setting.download
+    return {
+        'connect-timeout': str(download_setting.connectTimeout or 10),
+        'timeout': str(download_setting.readTimeout or 30),
+    }
 
 
 def init_download_options_with_proxy():
@@ -61,24 +98,81 @@ def init_download_options_with_proxy():
             options.update(options_on_cdn)
         return options
     else:
-        return options_on_cdn
+        return dict(options_on_cdn)
 
 
-def get_github_download_url(origin_url: str):
-    source = config.setting.network.githubDownloadSource
+def get_available_github_mirrors():
+    return list(github_override_map.keys()) + ['direct']
+
+
+def get_github_mirror_priority():
+    available_mirrors = get_available_github_mirrors()
+    priority = [name for name in config.setting.network.mirrorPriority or [] if name in available_mirrors]
+    return priority or [config.setting.network.githubDownloadSource]
+
+
+def get_github_mirror_url(origin_url: str, source: str):
     if source in github_override_map:
-        prefix = github_override_map[source]
-        url = origin_url.replace('https://github.com', prefix)
-        logger.info(f'using new url: {url}')
-        return url
-    logger.info(f'using origin url: {origin_url}')
+        return origin_url.replace('https://github.com', github_override_map[source])
     return origin_url
 
 
+def get_github_download_url(origin_url: str):
+    url = get_github_mirror_url(origin_url, get_github_mirror_priority()[0])
+    logger.info(f'using {"new" if url != origin_url else "origin"} url: {url}')
+    return url
+
+
+def try_switch_mirror(url: str):
+    """
+    get the url of next github mirror in mirror priority
+    :param url: url of current mirror
+    :return: url of next mirror, None if there is no more mirror
+    """
+    origin_url, current = url, 'direct'
+    for name, prefix in github_override_map.items():
+        if url.startswith(prefix):
+            origin_url, current = url.replace(prefix, 'https://github.com', 1), name
+            break
+    if not origin_url.startswith('https://github.com'):
+        return None
+    priority = get_github_mirror_priority()
+    if current not in priority:
+        return None
+    for name in priority[priority.index(current) + 1:]:
+        new_url = get_github_mirror_url(origin_url, name)
+        if new_url != url:
+            logger.info(f'switch github mirror from {current} to {name}, new url: {new_url}')
+            return new_url
+    return None
+
+
+def get_available_firmware_sources():
+    res = [
+        {'name': '根据系统代理自动决定', 'value': 'auto-detect'},
+        {'name': '[美国 Cloudflare CDN] - 自建代理服务器', 'value': 'cdn'},
+        {'name': '直连', 'value': 'direct'},
+    ]
+    for source in config.setting.network.customFirmwareSources or []:
+        res.append({'name': f'[自定义] - {source["name"]}', 'value': source['name'], 'url': source['url']})
+    return res
+
+
+def get_custom_firmware_source_url(name: str):
+    for source in config.setting.network.customFirmwareSources or []:
+        if source.get('name') == name and source.get('url'):
+            return source['url']
+
+
 def get_finial_url(origin_url: str):
     network_setting = config.setting.network
     if origin_url.startswith('https://api.github.com'):
         return get_finial_url_with_mode(origin_url, network_setting.githubApiMode)
+    custom_source_url = get_custom_firmware_source_url(network_setting.firmwareSource)
+    if custom_source_url and origin_url.startswith(firmware_base_url):
+        new_url = origin_url.replace(firmware_base_url, custom_source_url.rstrip('/') + '/')
+        logger.info(f'using custom firmware source url: {new_url}')
+        return new_url
     return get_finial_url_with_mode(origin_url, network_setting.firmwareSource)
 
 
@@ -112,6 +206,36 @@ def get_override_url(origin_url):
     return origin_url
 
 
+def is_mirror_url(url: str):
+    prefixes = list(github_override_map.values()) + list(url_override_map.values())
+    prefixes += [source['url'].rstrip('/') for source in config.setting.network.customFirmwareSources or []
+                 if source.get('url')]
+    return any(url.startswith(prefix) for prefix in prefixes)
+
+
+def clamp_download_connections(url: str, options: dict):
+    limit = max_connections_on_mirror if is_mirror_url(url) else max_connections_on_origin
+    for key in ('split', 'max-connection-per-server'):
+        if key in options and int(options[key]) > limit:
+            logger.info(f'clamp {key} of {url} from {options[key]} to {limit}')
+            options[key] = str(limit)
+    return options
+
+
+def get_download_source_name(url: str):
+    for name, prefix in github_override_map.items():
+        if url.startswith(prefix):
+            return f'GitHub 镜像 [{name}]'
+    for prefix in url_override_map.values():
+        if url.startswith(prefix):
+            return '自建代理服务器'
+    for source in config.setting.network.customFirmwareSources or []:
+        if source.get('url') and url.startswith(source['url'].rstrip('/')):
+            return f'自定义源 [{source["name"]}]'
+    from urllib.parse import urlparse
+    return urlparse(url).hostname or url
+
+
 def is_port_in_use(port: int) -> bool:
     import socket
     with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as s:
@@ -127,13 +251,127 @@ def get_available_port() -> int:
     return port
 
 
+def get_remote_file_validator(url: str, headers=None):
+    """
+    get ETag or Last-Modified of remote file, which can be used to check whether the file changed
+    :param url: url of remote file
+    :param headers: extra request headers, e.g. Referer or Cookie
+    :return: validator string or None
+    """
+    import time
+    cache_key = (url, tuple(sorted(headers.items())) if headers else None)
+    cached = remote_file_validator_cache.get(cache_key)
+    if cached and time.time() - cached[0] < remote_file_validator_cache_ttl:
+        logger.debug(f'using cached validator of url: {url}')
+        return cached[1]
+    validator = None
+    try:
+        with session.cache_disabled():
+            resp = session.head(url, headers=headers, allow_redirects=True, timeout=5)
+        validator = resp.headers.get('ETag') or resp.headers.get('Last-Modified')
+    except Exception as e:
+        logger.warning(f'fail to get validator of url: {url}, msg: {str(e)}')
+    remote_file_validator_cache[cache_key] = (time.time(), validator)
+    return validator
+
+
+def _check_url_connectivity(name: str, url: str):
+    import time
+    start = time.time()
+    try:
+        resp = requests.head(url, timeout=5, allow_redirects=True)
+        return {'name': name, 'url': url, 'ok': True, 'status_code': resp.status_code,
+                'latency': int((time.time() - start) * 1000), 'error': None}
+    except Exception as e:
+        return {'name': name, 'url': url, 'ok': False, 'status_code': None,
+                'latency': int((time.time() - start) * 1000), 'error': str(e)}
+
+
+def _check_doh_resolution(name: str, url: str, domain='github.com'):
+    import time
+    start = time.time()
+    try:
+        resp = requests.get(url, params={'name': domain, 'type': 'A'}, headers={'accept': 'application/dns-json'},
+                            timeout=5)
+        answers = [item['data'] for item in resp.json().get('Answer') or [] if item.get('type') == 1]
+        return {'name': name, 'url': url, 'ok': bool(answers), 'status_code': resp.status_code,
+                'latency': int((time.time() - start) * 1000),
+                'error': None if answers else f'no A record of {domain} resolved'}
+    except Exception as e:
+        return {'name': name, 'url': url, 'ok': False, 'status_code': None,
+                'latency': int((time.time() - start) * 1000), 'error': str(e)}
+
+
+def run_network_diagnostics():
+    from concurrent.futures import ThreadPoolExecutor
+    targets = [('GitHub Api', 'https://api.github.com'), ('GitHub', 'https://github.com')]
+    targets += [(f'CDN: {k}', v) for k, v in url_override_map.items()]
+    targets += [(f'GitHub 下载源: {k}', v) for k, v in github_override_map.items()]
+    with ThreadPoolExecutor(max_workers=len(targets) + len(doh_servers)) as executor:
+        url_futures = [executor.submit(_check_url_connectivity, *t) for t in targets]
+        doh_futures = [executor.submit(_check_doh_resolution, f'DoH: {k}', v) for k, v in doh_servers.items()]
+        res = [f.result() for f in url_futures + doh_futures]
+    from module.downloader import aria2_port
+    if aria2_port:
+        res.append({'name': 'aria2 rpc', 'url': f'http://127.0.0.1:{aria2_port}', 'ok': is_port_in_use(aria2_port),
+                    'status_code': None, 'latency': None, 'error': None})
+    logger.info(f'network diagnostics result: {res}')
+    return res
+
+
+def stream_download(url: str, target_file, chunk_size=1024 * 1024, timeout=None):
+    """
+    download small files without aria2, content is written to disk chunk by chunk
+    """
+    if timeout is None:
+        timeout = (config.setting.download.connectTimeout or 10, config.setting.download.readTimeout or 30)
+    with requests.get(url, stream=True, timeout=timeout) as resp:
+        resp.raise_for_status()
+        with open(target_file, 'wb') as f:
+            for chunk in resp.iter_content(chunk_size=chunk_size):
+                f.write(chunk)
+    return target_file
+
+
+def update_github_api_rate_status(resp):
+    import time
+    if getattr(resp, 'from_cache', False):
+        return
+    github_api_rate_status['request_count'] += 1
+    headers = resp.headers
+    for key, header in (('limit', 'X-RateLimit-Limit'), ('remaining', 'X-RateLimit-Remaining'),
+                        ('reset', 'X-RateLimit-Reset')):
+        value = headers.get(header)
+        if value is not None and str(value).isdigit():
+            github_api_rate_status[key] = int(value)
+    if resp.status_code in (403, 429):
+        github_api_rate_status['last_limited_time'] = int(time.time())
+
+
+def is_github_api_rate_nearly_exhausted():
+    import time
+    remaining = github_api_rate_status['remaining']
+    reset = github_api_rate_status['reset']
+    if remaining is None or remaining > github_api_rate_low_watermark:
+        return False
+    return reset is None or reset > time.time()
+
+
+def get_api_rate_status():
+    return dict(github_api_rate_status, nearly_exhausted=is_github_api_rate_nearly_exhausted())
+
+
 def request_github_api(url: str):
     global github_api_fallback_flag
     logger.info(f'requesting github api: {url}')
     from module.msg_notifier import send_notify
-    if config.setting.network.githubApiMode != 'cdn' and not github_api_fallback_flag:
+    if config.setting.network.githubApiMode != 'cdn' and not github_api_fallback_flag \
+            and is_github_api_rate_nearly_exhausted():
+        logger.info(f'github api rate is nearly exhausted, status: {github_api_rate_status}, using cdn.')
+    elif config.setting.network.githubApiMode != 'cdn' and not github_api_fallback_flag:
         try:
             resp = session.get(url, timeout=5)
+            update_github_api_rate_status(resp)
             data = resp.json()
             if isinstance(data, dict) and 'message' in data:
                 logger.warning(f'github api message: {data["message"]}')